@@ -0,0 +1,192 @@
+use crate::{num::ArithmeticOps, vector3d::Vec3d};
+
+// Axis aligned bounding box
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aabb<T>
+where
+    T: ArithmeticOps,
+{
+    pub min: Vec3d<T>,
+    pub max: Vec3d<T>,
+}
+
+impl<T> PartialEq for Aabb<T>
+where
+    T: ArithmeticOps,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.min == other.min && self.max == other.max
+    }
+}
+
+fn min_of<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max_of<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+impl<T> Aabb<T>
+where
+    T: ArithmeticOps + PartialOrd,
+{
+    pub fn new(min: Vec3d<T>, max: Vec3d<T>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_point(point: &Vec3d<T>) -> Self {
+        Self {
+            min: point.clone(),
+            max: point.clone(),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: Vec3d::new(
+                min_of(self.min.x.clone(), other.min.x.clone()),
+                min_of(self.min.y.clone(), other.min.y.clone()),
+                min_of(self.min.z.clone(), other.min.z.clone()),
+            ),
+            max: Vec3d::new(
+                max_of(self.max.x.clone(), other.max.x.clone()),
+                max_of(self.max.y.clone(), other.max.y.clone()),
+                max_of(self.max.z.clone(), other.max.z.clone()),
+            ),
+        }
+    }
+
+    pub fn grow(&self, point: &Vec3d<T>) -> Self {
+        self.union(&Self::from_point(point))
+    }
+
+    pub fn expand(&self, margin: T) -> Self {
+        Self {
+            min: self.min.each_sub(margin.clone()),
+            max: self.max.each_add(margin),
+        }
+    }
+
+    pub fn extent(&self) -> Vec3d<T> {
+        self.max.clone() - self.min.clone()
+    }
+
+    pub fn centroid(&self) -> Vec3d<T> {
+        let two = T::one() + T::one();
+        (self.min.clone() + self.max.clone()).each_div(two)
+    }
+
+    pub fn surface_area(&self) -> T {
+        let e = self.extent();
+        let half = e.x.clone() * e.y.clone() + e.y.clone() * e.z.clone() + e.z * e.x;
+        half.clone() + half
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let e = self.extent();
+        if e.x >= e.y && e.x >= e.z {
+            0
+        } else if e.y >= e.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn contains(&self, point: &Vec3d<T>) -> bool {
+        (0..3).all(|axis| {
+            let p = point.component(axis);
+            p >= self.min.component(axis) && p <= self.max.component(axis)
+        })
+    }
+
+    // Slab test, returns the entry distance along the ray if the box is hit within [t_min, t_max].
+    // inv_dir is 1/dir for each component, NaN produced by a zero direction are ignored.
+    pub fn hit(&self, origin: &Vec3d<T>, inv_dir: &Vec3d<T>, t_min: T, t_max: T) -> Option<T> {
+        let mut t_enter = t_min;
+        let mut t_exit = t_max;
+        for axis in 0..3 {
+            let o = origin.component(axis);
+            let inv = inv_dir.component(axis);
+            let t0 = (self.min.component(axis) - o.clone()) * inv.clone();
+            let t1 = (self.max.component(axis) - o) * inv.clone();
+            let (near, far) = if inv < T::zero() { (t1, t0) } else { (t0, t1) };
+            if near > t_enter {
+                t_enter = near;
+            }
+            if far < t_exit {
+                t_exit = far;
+            }
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+        Some(t_enter)
+    }
+}
+
+pub type AabbF32 = Aabb<f32>;
+pub type AabbF64 = Aabb<f64>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb<f64> {
+        Aabb::new(Vec3d::new(-1.0, -1.0, -1.0), Vec3d::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_union() {
+        let b1 = unit_box();
+        let b2 = Aabb::new(Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(3.0, 2.0, 0.5));
+        assert_eq!(
+            b1.union(&b2),
+            Aabb::new(Vec3d::new(-1.0, -1.0, -1.0), Vec3d::new(3.0, 2.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn test_surface_area() {
+        assert_eq!(unit_box().surface_area(), 24.0);
+        let flat = Aabb::new(Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(2.0, 3.0, 0.0));
+        assert_eq!(flat.surface_area(), 12.0);
+    }
+
+    #[test]
+    fn test_centroid_and_axis() {
+        let b = Aabb::new(Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(2.0, 6.0, 4.0));
+        assert_eq!(b.centroid(), Vec3d::new(1.0, 3.0, 2.0));
+        assert_eq!(b.longest_axis(), 1);
+        assert!(b.contains(&Vec3d::new(1.0, 1.0, 1.0)));
+        assert!(!b.contains(&Vec3d::new(-1.0, 1.0, 1.0)));
+        assert!(b.expand(1.0).contains(&Vec3d::new(-1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn test_hit() {
+        let b = unit_box();
+        let origin = Vec3d::new(-5.0, 0.0, 0.0);
+        let inv_dir = Vec3d::new(1.0 / 1.0, 1.0 / 0.0, 1.0 / 0.0);
+        assert_eq!(b.hit(&origin, &inv_dir, 0.0, f64::INFINITY), Some(4.0));
+        assert_eq!(b.hit(&origin, &inv_dir, 0.0, 3.0), None);
+
+        let inside = Vec3d::new(0.0, 0.0, 0.0);
+        assert_eq!(b.hit(&inside, &inv_dir, 0.0, f64::INFINITY), Some(0.0));
+
+        let miss = Vec3d::new(-5.0, 2.0, 0.0);
+        assert_eq!(b.hit(&miss, &inv_dir, 0.0, f64::INFINITY), None);
+
+        let on_edge = Vec3d::new(-5.0, 1.0, 1.0);
+        assert!(b.hit(&on_edge, &inv_dir, 0.0, f64::INFINITY).is_some());
+    }
+}
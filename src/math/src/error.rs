@@ -5,3 +5,11 @@ pub enum Vect3dError {
     #[error("Division by zero.")]
     DivideByZero,
 }
+
+#[derive(Error,Debug)]
+pub enum MatrixError {
+    #[error("The matrix is not invertible.")]
+    NotInvertible,
+    #[error("Degenerate look at: {0}")]
+    DegenerateLookAt(&'static str),
+}
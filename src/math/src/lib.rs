@@ -2,6 +2,10 @@ pub mod vector3d;
 pub mod vector2d;
 pub mod num;
 pub mod error;
+pub mod aabb;
+pub mod matrix3;
+pub mod matrix4;
+pub mod transform;
 
 #[macro_use]
 extern crate serde_derive;
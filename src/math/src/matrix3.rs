@@ -0,0 +1,272 @@
+use std::{fmt::Display, ops::Mul};
+
+use crate::{
+    error::MatrixError,
+    num::{ArithmeticOps, Sqrt, Trigo},
+    vector3d::Vec3d,
+};
+
+// Row major 3x3 matrix, vectors are column vectors (m * v).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    pub m: [[T; 3]; 3],
+}
+
+impl<T> Clone for Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    fn clone(&self) -> Self {
+        Self { m: self.m.clone() }
+    }
+}
+
+impl<T> Default for Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<T> PartialEq for Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.m == other.m
+    }
+}
+
+impl<T> Display for Mat3<T>
+where
+    T: Display + ArithmeticOps,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let m = &self.m;
+        write!(
+            f,
+            "Matrix([{},{},{}],[{},{},{}],[{},{},{}])",
+            m[0][0], m[0][1], m[0][2], m[1][0], m[1][1], m[1][2], m[2][0], m[2][1], m[2][2]
+        )
+    }
+}
+
+impl<T> Mul for Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl<T> Mul for &Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    type Output = Mat3<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m: [[T; 3]; 3] = Default::default();
+        for (row, line) in m.iter_mut().enumerate() {
+            for (col, value) in line.iter_mut().enumerate() {
+                *value = (0..3).fold(T::zero(), |acc, k| {
+                    acc + self.m[row][k].clone() * rhs.m[k][col].clone()
+                });
+            }
+        }
+        Mat3 { m }
+    }
+}
+
+impl<T> Mul<Vec3d<T>> for Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    type Output = Vec3d<T>;
+
+    fn mul(self, rhs: Vec3d<T>) -> Self::Output {
+        self.transform(&rhs)
+    }
+}
+
+impl<T> Mat3<T>
+where
+    T: ArithmeticOps,
+{
+    pub fn new(m: [[T; 3]; 3]) -> Self {
+        Self { m }
+    }
+
+    pub fn from_rows(row0: Vec3d<T>, row1: Vec3d<T>, row2: Vec3d<T>) -> Self {
+        Self {
+            m: [
+                [row0.x, row0.y, row0.z],
+                [row1.x, row1.y, row1.z],
+                [row2.x, row2.y, row2.z],
+            ],
+        }
+    }
+
+    pub fn from_columns(col0: Vec3d<T>, col1: Vec3d<T>, col2: Vec3d<T>) -> Self {
+        Self::from_rows(col0, col1, col2).transpose()
+    }
+
+    pub fn identity() -> Self {
+        Self::scaling(&Vec3d::with_value(T::one()))
+    }
+
+    pub fn zero() -> Self {
+        Self {
+            m: Default::default(),
+        }
+    }
+
+    pub fn scaling(scale: &Vec3d<T>) -> Self {
+        let mut m = Self::zero();
+        m.m[0][0] = scale.x.clone();
+        m.m[1][1] = scale.y.clone();
+        m.m[2][2] = scale.z.clone();
+        m
+    }
+
+    pub fn row(&self, row: usize) -> Vec3d<T> {
+        Vec3d::new(self.m[row][0].clone(), self.m[row][1].clone(), self.m[row][2].clone())
+    }
+
+    pub fn column(&self, col: usize) -> Vec3d<T> {
+        Vec3d::new(self.m[0][col].clone(), self.m[1][col].clone(), self.m[2][col].clone())
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_rows(self.column(0), self.column(1), self.column(2))
+    }
+
+    pub fn transform(&self, v: &Vec3d<T>) -> Vec3d<T> {
+        Vec3d::new(self.row(0).dot(v), self.row(1).dot(v), self.row(2).dot(v))
+    }
+
+    pub fn determinant(&self) -> T {
+        let m = &self.m;
+        m[0][0].clone() * self.cofactor(0, 0)
+            + m[0][1].clone() * self.cofactor(0, 1)
+            + m[0][2].clone() * self.cofactor(0, 2)
+    }
+
+    // Signed determinant of the 2x2 matrix left when removing a row and a column.
+    pub fn cofactor(&self, row: usize, col: usize) -> T {
+        let rows: Vec<usize> = (0..3).filter(|r| *r != row).collect();
+        let cols: Vec<usize> = (0..3).filter(|c| *c != col).collect();
+        let m = &self.m;
+        let minor = m[rows[0]][cols[0]].clone() * m[rows[1]][cols[1]].clone()
+            - m[rows[0]][cols[1]].clone() * m[rows[1]][cols[0]].clone();
+        if (row + col).is_multiple_of(2) {
+            minor
+        } else {
+            T::zero() - minor
+        }
+    }
+
+    pub fn inverse(&self) -> Result<Self, MatrixError> {
+        let det = self.determinant();
+        if det == T::zero() {
+            return Err(MatrixError::NotInvertible);
+        }
+        // inverse = transposed cofactor matrix / determinant
+        let mut m: [[T; 3]; 3] = Default::default();
+        for (row, line) in m.iter_mut().enumerate() {
+            for (col, value) in line.iter_mut().enumerate() {
+                *value = self.cofactor(col, row) / det.clone();
+            }
+        }
+        Ok(Self { m })
+    }
+}
+
+impl<T> Mat3<T>
+where
+    T: ArithmeticOps + Trigo + Sqrt,
+{
+    // Rotation of `angle` radians around `axis` (right hand rule).
+    // https://en.wikipedia.org/wiki/Rotation_matrix#Rotation_matrix_from_axis_and_angle
+    pub fn rotation(axis: &Vec3d<T>, angle: T) -> Self {
+        let axis = axis.norm().unwrap_or_else(|_| Vec3d::z_axis());
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        let cos = angle.clone().cos();
+        let sin = angle.sin();
+        let t = T::one() - cos.clone();
+        Self {
+            m: [
+                [
+                    t.clone() * x.clone() * x.clone() + cos.clone(),
+                    t.clone() * x.clone() * y.clone() - sin.clone() * z.clone(),
+                    t.clone() * x.clone() * z.clone() + sin.clone() * y.clone(),
+                ],
+                [
+                    t.clone() * x.clone() * y.clone() + sin.clone() * z.clone(),
+                    t.clone() * y.clone() * y.clone() + cos.clone(),
+                    t.clone() * y.clone() * z.clone() - sin.clone() * x.clone(),
+                ],
+                [
+                    t.clone() * x.clone() * z.clone() - sin.clone() * y.clone(),
+                    t.clone() * y.clone() * z.clone() + sin * x,
+                    t * z.clone() * z + cos,
+                ],
+            ],
+        }
+    }
+}
+
+pub type Mat3F32 = Mat3<f32>;
+pub type Mat3F64 = Mat3<f64>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_equal(a: &Mat3<f64>, b: &Mat3<f64>) -> bool {
+        (0..3).all(|r| (0..3).all(|c| (a.m[r][c] - b.m[r][c]).abs() < 1e-9))
+    }
+
+    #[test]
+    fn test_mul() {
+        let a: Mat3<i32> = Mat3::new([[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+        assert_eq!(&a * &Mat3::identity(), a);
+        let b: Mat3<i32> = Mat3::new([[0, 1, 0], [1, 0, 0], [0, 0, 2]]);
+        assert_eq!(a.clone() * b, Mat3::new([[2, 1, 6], [5, 4, 12], [8, 7, 18]]));
+        assert_eq!(a * Vec3d::new(1, 0, -1), Vec3d::new(-2, -2, -2));
+    }
+
+    #[test]
+    fn test_transpose_and_determinant() {
+        let a: Mat3<i32> = Mat3::new([[2, 0, 1], [1, 3, 2], [1, 1, 2]]);
+        assert_eq!(a.transpose(), Mat3::new([[2, 1, 1], [0, 3, 1], [1, 2, 2]]));
+        assert_eq!(a.determinant(), 6);
+        assert_eq!(a.transpose().determinant(), 6);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let a: Mat3<f64> = Mat3::new([[2.0, 0.0, 1.0], [1.0, 3.0, 2.0], [1.0, 1.0, 2.0]]);
+        let inv = a.inverse().unwrap();
+        assert!(approx_equal(&(&a * &inv), &Mat3::identity()));
+
+        let singular: Mat3<f64> = Mat3::new([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 0.0]]);
+        assert!(singular.inverse().is_err());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let r: Mat3<f64> = Mat3::rotation(&Vec3d::z_axis(), std::f64::consts::FRAC_PI_2);
+        let v = r.transform(&Vec3d::x_axis());
+        assert!((v - Vec3d::y_axis()).mag() < 1e-9);
+        assert!(approx_equal(&r.inverse().unwrap(), &r.transpose()));
+    }
+}
@@ -0,0 +1,325 @@
+use std::{fmt::Display, ops::Mul};
+
+use crate::{
+    error::MatrixError,
+    matrix3::Mat3,
+    num::{ArithmeticOps, Sqrt, Trigo},
+    vector3d::Vec3d,
+};
+
+// Row major 4x4 matrix for homogeneous coordinates, vectors are column vectors (m * v).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    pub m: [[T; 4]; 4],
+}
+
+impl<T> Clone for Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    fn clone(&self) -> Self {
+        Self { m: self.m.clone() }
+    }
+}
+
+impl<T> Default for Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<T> PartialEq for Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.m == other.m
+    }
+}
+
+impl<T> Display for Mat4<T>
+where
+    T: Display + ArithmeticOps,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Matrix(")?;
+        for (row, line) in self.m.iter().enumerate() {
+            if row > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "[{},{},{},{}]", line[0], line[1], line[2], line[3])?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<T> Mul for Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl<T> Mul for &Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    type Output = Mat4<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m: [[T; 4]; 4] = Default::default();
+        for (row, line) in m.iter_mut().enumerate() {
+            for (col, value) in line.iter_mut().enumerate() {
+                *value = (0..4).fold(T::zero(), |acc, k| {
+                    acc + self.m[row][k].clone() * rhs.m[k][col].clone()
+                });
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+impl<T> Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    pub fn new(m: [[T; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        let mut m = Self::zero();
+        for i in 0..4 {
+            m.m[i][i] = T::one();
+        }
+        m
+    }
+
+    pub fn zero() -> Self {
+        Self {
+            m: Default::default(),
+        }
+    }
+
+    // Upper left 3x3 linear part and translation.
+    pub fn from_linear(linear: &Mat3<T>, translation: &Vec3d<T>) -> Self {
+        let mut m = Self::identity();
+        for row in 0..3 {
+            for col in 0..3 {
+                m.m[row][col] = linear.m[row][col].clone();
+            }
+            m.m[row][3] = translation.component(row);
+        }
+        m
+    }
+
+    pub fn linear(&self) -> Mat3<T> {
+        let mut linear = Mat3::zero();
+        for row in 0..3 {
+            for col in 0..3 {
+                linear.m[row][col] = self.m[row][col].clone();
+            }
+        }
+        linear
+    }
+
+    pub fn translation(offset: &Vec3d<T>) -> Self {
+        Self::from_linear(&Mat3::identity(), offset)
+    }
+
+    pub fn scaling(scale: &Vec3d<T>) -> Self {
+        Self::from_linear(&Mat3::scaling(scale), &Vec3d::zero())
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m: [[T; 4]; 4] = Default::default();
+        for (row, line) in m.iter_mut().enumerate() {
+            for (col, value) in line.iter_mut().enumerate() {
+                *value = self.m[col][row].clone();
+            }
+        }
+        Self { m }
+    }
+
+    // Signed determinant of the 3x3 matrix left when removing a row and a column.
+    pub fn cofactor(&self, row: usize, col: usize) -> T {
+        let rows: Vec<usize> = (0..4).filter(|r| *r != row).collect();
+        let cols: Vec<usize> = (0..4).filter(|c| *c != col).collect();
+        let mut minor: Mat3<T> = Mat3::zero();
+        for (i, r) in rows.iter().enumerate() {
+            for (j, c) in cols.iter().enumerate() {
+                minor.m[i][j] = self.m[*r][*c].clone();
+            }
+        }
+        let det = minor.determinant();
+        if (row + col).is_multiple_of(2) {
+            det
+        } else {
+            T::zero() - det
+        }
+    }
+
+    pub fn determinant(&self) -> T {
+        (0..4).fold(T::zero(), |acc, col| acc + self.m[0][col].clone() * self.cofactor(0, col))
+    }
+
+    pub fn inverse(&self) -> Result<Self, MatrixError> {
+        let det = self.determinant();
+        if det == T::zero() {
+            return Err(MatrixError::NotInvertible);
+        }
+        // inverse = transposed cofactor matrix / determinant
+        let mut m: [[T; 4]; 4] = Default::default();
+        for (row, line) in m.iter_mut().enumerate() {
+            for (col, value) in line.iter_mut().enumerate() {
+                *value = self.cofactor(col, row) / det.clone();
+            }
+        }
+        Ok(Self { m })
+    }
+
+    // Point (w = 1), the result is divided by w for projective matrices.
+    pub fn transform_point(&self, p: &Vec3d<T>) -> Vec3d<T> {
+        let row = |r: usize| {
+            self.m[r][0].clone() * p.x.clone()
+                + self.m[r][1].clone() * p.y.clone()
+                + self.m[r][2].clone() * p.z.clone()
+                + self.m[r][3].clone()
+        };
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        if w == T::one() || w == T::zero() {
+            Vec3d::new(x, y, z)
+        } else {
+            Vec3d::new(x, y, z).each_div(w)
+        }
+    }
+
+    // Direction (w = 0), not affected by the translation.
+    pub fn transform_vector(&self, v: &Vec3d<T>) -> Vec3d<T> {
+        self.linear().transform(v)
+    }
+
+    // Normals are transformed by the inverse transpose to stay perpendicular to the surface,
+    // `self` is expected to be the inverse of the transformation applied to the points.
+    pub fn transform_normal_with_inverse(&self, n: &Vec3d<T>) -> Vec3d<T> {
+        self.linear().transpose().transform(n)
+    }
+}
+
+impl<T> Mat4<T>
+where
+    T: ArithmeticOps + Trigo + Sqrt,
+{
+    // Rotation of `angle` radians around `axis` (right hand rule).
+    pub fn rotation(axis: &Vec3d<T>, angle: T) -> Self {
+        Self::from_linear(&Mat3::rotation(axis, angle), &Vec3d::zero())
+    }
+
+    // Maps the camera space (x right, y up, z forward) to the world,
+    // with the camera at `eye` looking at `target`.
+    pub fn look_at(eye: &Vec3d<T>, target: &Vec3d<T>, up: &Vec3d<T>) -> Result<Self, MatrixError> {
+        let forward = (target.clone() - eye.clone())
+            .norm()
+            .map_err(|_| MatrixError::DegenerateLookAt("eye and target are the same point"))?;
+        let right = forward
+            .cross(up.clone())
+            .norm()
+            .map_err(|_| MatrixError::DegenerateLookAt("up is parallel to the view direction"))?;
+        let true_up = right.cross(forward.clone());
+        Ok(Self::from_linear(&Mat3::from_columns(right, true_up, forward), eye))
+    }
+}
+
+pub type Mat4F32 = Mat4<f32>;
+pub type Mat4F64 = Mat4<f64>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_equal(a: &Mat4<f64>, b: &Mat4<f64>) -> bool {
+        (0..4).all(|r| (0..4).all(|c| (a.m[r][c] - b.m[r][c]).abs() < 1e-9))
+    }
+
+    fn approx_equal_vec(a: &Vec3d<f64>, b: &Vec3d<f64>) -> bool {
+        (a.clone() - b.clone()).mag() < 1e-9
+    }
+
+    #[test]
+    fn test_determinant_and_inverse() {
+        let a: Mat4<f64> = Mat4::new([
+            [1.0, 0.0, 2.0, -1.0],
+            [3.0, 0.0, 0.0, 5.0],
+            [2.0, 1.0, 4.0, -3.0],
+            [1.0, 0.0, 5.0, 0.0],
+        ]);
+        assert!((a.determinant() - 30.0).abs() < 1e-9);
+        let inv = a.inverse().unwrap();
+        assert!(approx_equal(&(&a * &inv), &Mat4::identity()));
+        assert!(approx_equal(&(&inv * &a), &Mat4::identity()));
+        assert!(Mat4::<f64>::zero().inverse().is_err());
+    }
+
+    #[test]
+    fn test_transpose() {
+        let a: Mat4<i32> = Mat4::new([[1, 2, 3, 4], [5, 6, 7, 8], [9, 10, 11, 12], [13, 14, 15, 16]]);
+        assert_eq!(a.transpose().m[0], [1, 5, 9, 13]);
+        assert_eq!(a.transpose().transpose(), a);
+    }
+
+    #[test]
+    fn test_transform() {
+        let t = Mat4::translation(&Vec3d::new(1.0, 2.0, 3.0));
+        let s = Mat4::scaling(&Vec3d::new(2.0, 2.0, 2.0));
+        let p = Vec3d::new(1.0, 1.0, 1.0);
+        // Scale first, then translate
+        assert_eq!((&t * &s).transform_point(&p), Vec3d::new(3.0, 4.0, 5.0));
+        assert_eq!((&t * &s).transform_vector(&p), Vec3d::new(2.0, 2.0, 2.0));
+
+        let r = Mat4::rotation(&Vec3d::x_axis(), std::f64::consts::FRAC_PI_2);
+        assert!(approx_equal_vec(&r.transform_point(&Vec3d::y_axis()), &Vec3d::z_axis()));
+    }
+
+    #[test]
+    fn test_transform_normal() {
+        // Squash along x, the normal of the plane x + y = 0 must stay perpendicular to it
+        let m: Mat4<f64> = Mat4::scaling(&Vec3d::new(0.5, 1.0, 1.0));
+        let inverse = m.inverse().unwrap();
+        let in_plane = Vec3d::new(1.0, -1.0, 0.0);
+        let normal = Vec3d::new(1.0, 1.0, 0.0);
+        let new_in_plane = m.transform_vector(&in_plane);
+        let new_normal = inverse.transform_normal_with_inverse(&normal);
+        assert!(new_in_plane.dot(&new_normal).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_look_at() {
+        let eye = Vec3d::new(0.0, 0.0, 0.0);
+        let m = Mat4::look_at(&eye, &Vec3d::new(5.0, 0.0, 0.0), &Vec3d::z_axis()).unwrap();
+        // Same basis as the default camera: direction x, up z, right -y
+        assert!(approx_equal_vec(&m.transform_vector(&Vec3d::z_axis()), &Vec3d::x_axis()));
+        assert!(approx_equal_vec(&m.transform_vector(&Vec3d::y_axis()), &Vec3d::z_axis()));
+        assert!(approx_equal_vec(&m.transform_vector(&Vec3d::x_axis()), &-Vec3d::y_axis()));
+
+        assert!(Mat4::look_at(&eye, &Vec3d::z_axis(), &Vec3d::z_axis()).is_err());
+        assert!(Mat4::look_at(&eye, &eye, &Vec3d::z_axis()).is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        let m: Mat4<f64> = Mat4::translation(&Vec3d::new(1.0, 2.0, 3.0));
+        let json = serde_json::to_string(&m).unwrap();
+        let back: Mat4<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(m, back);
+    }
+}
@@ -0,0 +1,226 @@
+use std::{convert::TryFrom, ops::Mul};
+
+use crate::{
+    error::MatrixError,
+    matrix4::Mat4,
+    num::{ArithmeticOps, Sqrt, Trigo},
+    vector3d::Vec3d,
+};
+
+// Invertible transformation keeping both the matrix and its inverse,
+// serialized as the matrix alone.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "Mat4<T>", into = "Mat4<T>")]
+pub struct Transform<T>
+where
+    T: ArithmeticOps,
+{
+    matrix: Mat4<T>,
+    inverse: Mat4<T>,
+}
+
+impl<T> Clone for Transform<T>
+where
+    T: ArithmeticOps,
+{
+    fn clone(&self) -> Self {
+        Self {
+            matrix: self.matrix.clone(),
+            inverse: self.inverse.clone(),
+        }
+    }
+}
+
+impl<T> Default for Transform<T>
+where
+    T: ArithmeticOps,
+{
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<T> PartialEq for Transform<T>
+where
+    T: ArithmeticOps,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.matrix == other.matrix
+    }
+}
+
+impl<T> TryFrom<Mat4<T>> for Transform<T>
+where
+    T: ArithmeticOps,
+{
+    type Error = MatrixError;
+
+    fn try_from(matrix: Mat4<T>) -> Result<Self, Self::Error> {
+        Self::new(matrix)
+    }
+}
+
+impl<T> From<Transform<T>> for Mat4<T>
+where
+    T: ArithmeticOps,
+{
+    fn from(transform: Transform<T>) -> Self {
+        transform.matrix
+    }
+}
+
+// `a * b` applies b first, then a.
+impl<T> Mul for &Transform<T>
+where
+    T: ArithmeticOps,
+{
+    type Output = Transform<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Transform {
+            matrix: &self.matrix * &rhs.matrix,
+            inverse: &rhs.inverse * &self.inverse,
+        }
+    }
+}
+
+impl<T> Mul for Transform<T>
+where
+    T: ArithmeticOps,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl<T> Transform<T>
+where
+    T: ArithmeticOps,
+{
+    pub fn new(matrix: Mat4<T>) -> Result<Self, MatrixError> {
+        let inverse = matrix.inverse()?;
+        Ok(Self { matrix, inverse })
+    }
+
+    pub fn identity() -> Self {
+        Self {
+            matrix: Mat4::identity(),
+            inverse: Mat4::identity(),
+        }
+    }
+
+    pub fn translation(offset: &Vec3d<T>) -> Self {
+        Self {
+            matrix: Mat4::translation(offset),
+            inverse: Mat4::translation(&(Vec3d::zero() - offset.clone())),
+        }
+    }
+
+    pub fn scaling(scale: &Vec3d<T>) -> Result<Self, MatrixError> {
+        Self::new(Mat4::scaling(scale))
+    }
+
+    pub fn matrix(&self) -> &Mat4<T> {
+        &self.matrix
+    }
+
+    pub fn inverse_matrix(&self) -> &Mat4<T> {
+        &self.inverse
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse.clone(),
+            inverse: self.matrix.clone(),
+        }
+    }
+
+    // Applies `self` then `next`.
+    pub fn then(&self, next: &Self) -> Self {
+        next * self
+    }
+
+    pub fn transform_point(&self, p: &Vec3d<T>) -> Vec3d<T> {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn transform_vector(&self, v: &Vec3d<T>) -> Vec3d<T> {
+        self.matrix.transform_vector(v)
+    }
+
+    // The result is not normalized.
+    pub fn transform_normal(&self, n: &Vec3d<T>) -> Vec3d<T> {
+        self.inverse.transform_normal_with_inverse(n)
+    }
+
+    pub fn inverse_transform_point(&self, p: &Vec3d<T>) -> Vec3d<T> {
+        self.inverse.transform_point(p)
+    }
+
+    pub fn inverse_transform_vector(&self, v: &Vec3d<T>) -> Vec3d<T> {
+        self.inverse.transform_vector(v)
+    }
+
+    pub fn inverse_transform_normal(&self, n: &Vec3d<T>) -> Vec3d<T> {
+        self.matrix.transform_normal_with_inverse(n)
+    }
+}
+
+impl<T> Transform<T>
+where
+    T: ArithmeticOps + Trigo + Sqrt,
+{
+    pub fn rotation(axis: &Vec3d<T>, angle: T) -> Self {
+        let matrix = Mat4::rotation(axis, angle);
+        // Rotations are orthogonal
+        let inverse = matrix.transpose();
+        Self { matrix, inverse }
+    }
+
+    pub fn look_at(eye: &Vec3d<T>, target: &Vec3d<T>, up: &Vec3d<T>) -> Result<Self, MatrixError> {
+        Self::new(Mat4::look_at(eye, target, up)?)
+    }
+}
+
+pub type TransformF32 = Transform<f32>;
+pub type TransformF64 = Transform<f64>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_equal_vec(a: &Vec3d<f64>, b: &Vec3d<f64>) -> bool {
+        (a.clone() - b.clone()).mag() < 1e-9
+    }
+
+    #[test]
+    fn test_compose_and_inverse() {
+        let scale = Transform::scaling(&Vec3d::new(2.0, 1.0, 1.0)).unwrap();
+        let rotate = Transform::rotation(&Vec3d::z_axis(), std::f64::consts::FRAC_PI_2);
+        let translate = Transform::translation(&Vec3d::new(0.0, 0.0, 1.0));
+        let t = scale.then(&rotate).then(&translate);
+
+        let p = Vec3d::new(1.0, 0.0, 0.0);
+        let moved = t.transform_point(&p);
+        assert!(approx_equal_vec(&moved, &Vec3d::new(0.0, 2.0, 1.0)));
+        assert!(approx_equal_vec(&t.inverse_transform_point(&moved), &p));
+        assert!(approx_equal_vec(&t.inverse().transform_point(&moved), &p));
+        assert!(Transform::scaling(&Vec3d::new(0.0, 1.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        let t: Transform<f64> = Transform::translation(&Vec3d::new(1.0, 2.0, 3.0));
+        let json = serde_json::to_string(&t).unwrap();
+        let back: Transform<f64> = serde_json::from_str(&json).unwrap();
+        assert!(approx_equal_vec(
+            &back.inverse_transform_point(&Vec3d::new(1.0, 2.0, 3.0)),
+            &Vec3d::zero()
+        ));
+
+        let singular = serde_json::to_string(&Mat4::<f64>::zero()).unwrap();
+        assert!(serde_json::from_str::<Transform<f64>>(&singular).is_err());
+    }
+}
@@ -224,7 +224,7 @@ where T: ArithmeticOps + Trigo + Sqrt {
     }
 }
 
-impl<'a, T> Vec2d<T>
+impl<T> Vec2d<T>
 where
     T: ArithmeticOps + Sqrt,
 {
@@ -235,7 +235,7 @@ where
     }
 }
 
-impl<'a, T> Vec2d<T>
+impl<T> Vec2d<T>
 where
     T: ArithmeticOps + Sqrt,
 {
@@ -241,6 +241,16 @@ where
             z: t.clone(),
         }
     }
+
+    // 0 => x, 1 => y, 2 => z
+    pub fn component(&self, axis: usize) -> T {
+        match axis {
+            0 => self.x.clone(),
+            1 => self.y.clone(),
+            2 => self.z.clone(),
+            _ => panic!("Invalid axis {}", axis),
+        }
+    }
 }
 
 impl <T> Vec3d<T>
@@ -250,7 +260,7 @@ where T: ArithmeticOps + Trigo + Sqrt {
     }
 }
 
-impl<'a, T> Vec3d<T>
+impl<T> Vec3d<T>
 where
     T: ArithmeticOps + Sqrt,
 {
@@ -261,7 +271,7 @@ where
     }
 }
 
-impl<'a, T> Vec3d<T>
+impl<T> Vec3d<T>
 where
     T: ArithmeticOps + Sqrt,
 {
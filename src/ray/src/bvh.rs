@@ -0,0 +1,321 @@
+use crate::{
+    intersection::Intersection,
+    ray::Ray,
+    things::Thing,
+    vector::{BoundingBox, Vector3d},
+};
+
+// Above this number of things a leaf is always split.
+const MAX_THINGS_PER_LEAF: usize = 4;
+// Relative cost of traversing a node compared to intersecting a thing.
+const TRAVERSAL_COST: f64 = 0.125;
+
+enum BvhNodeKind {
+    Leaf { first: usize, count: usize },
+    Interior { left: usize, right: usize },
+}
+
+struct BvhNode {
+    bounding_box: BoundingBox,
+    kind: BvhNodeKind,
+}
+
+struct BuildItem {
+    thing_index: usize,
+    bounding_box: BoundingBox,
+    centroid: Vector3d,
+}
+
+// Anything with a distance along the ray that can be sorted by the hierarchy.
+pub trait Hit {
+    fn distance(&self) -> f64;
+}
+
+impl Hit for Intersection {
+    fn distance(&self) -> f64 {
+        Intersection::distance(self)
+    }
+}
+
+// Bounding volume hierarchy over indexed primitives (the things of a world, the triangles of a mesh...),
+// built with the surface area heuristic.
+// Primitives without bounding box are kept in a separate list and tested against every ray.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    thing_indices: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(things: &[Box<dyn Thing>]) -> Self {
+        Self::from_bounding_boxes(things.iter().map(|thing| thing.bounding_box()))
+    }
+
+    pub fn from_bounding_boxes<I>(bounding_boxes: I) -> Self
+    where
+        I: Iterator<Item = Option<BoundingBox>>,
+    {
+        let mut unbounded = Vec::new();
+        let mut items = Vec::new();
+        for (thing_index, bounding_box) in bounding_boxes.enumerate() {
+            match bounding_box {
+                Some(bounding_box) => items.push(BuildItem {
+                    thing_index,
+                    centroid: bounding_box.centroid(),
+                    bounding_box,
+                }),
+                None => unbounded.push(thing_index),
+            }
+        }
+
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            thing_indices: Vec::with_capacity(items.len()),
+            unbounded,
+        };
+        if !items.is_empty() {
+            bvh.build(&mut items);
+        }
+        bvh
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn unbounded(&self) -> &[usize] {
+        &self.unbounded
+    }
+
+    // Depth first, with an explicit stack so that a deep hierarchy cannot overflow the thread stack.
+    fn build(&mut self, items: &mut [BuildItem]) {
+        // Items still to place, with the interior node waiting for them and whether they are its left child
+        let mut pending = vec![(0, items.len(), None::<(usize, bool)>)];
+        while let Some((start, end, parent)) = pending.pop() {
+            let items = &mut items[start..end];
+            let bounding_box = items
+                .iter()
+                .skip(1)
+                .fold(items[0].bounding_box.clone(), |acc, item| acc.union(&item.bounding_box));
+
+            let node_index = self.nodes.len();
+            if let Some((parent, is_left)) = parent {
+                if let BvhNodeKind::Interior { left, right } = &mut self.nodes[parent].kind {
+                    *(if is_left { left } else { right }) = node_index;
+                }
+            }
+            match Self::find_split(items, &bounding_box) {
+                Some((axis, split)) => {
+                    self.nodes.push(BvhNode {
+                        bounding_box,
+                        kind: BvhNodeKind::Interior { left: 0, right: 0 },
+                    });
+                    Self::sort_along(items, axis);
+                    // The left child is built first, right after its parent
+                    pending.push((start + split, end, Some((node_index, false))));
+                    pending.push((start, start + split, Some((node_index, true))));
+                }
+                None => {
+                    let first = self.thing_indices.len();
+                    self.thing_indices.extend(items.iter().map(|item| item.thing_index));
+                    self.nodes.push(BvhNode {
+                        bounding_box,
+                        kind: BvhNodeKind::Leaf {
+                            first,
+                            count: items.len(),
+                        },
+                    });
+                }
+            }
+        }
+    }
+
+    fn widest_centroid_axis(items: &[BuildItem]) -> usize {
+        let spread = |axis: usize| {
+            let values = items.iter().map(|item| item.centroid.component(axis));
+            values.clone().fold(f64::NEG_INFINITY, f64::max) - values.fold(f64::INFINITY, f64::min)
+        };
+        (1..3).fold(0, |widest, axis| if spread(axis) > spread(widest) { axis } else { widest })
+    }
+
+    fn sort_along(items: &mut [BuildItem], axis: usize) {
+        items.sort_by(|a, b| {
+            a.centroid
+                .component(axis)
+                .partial_cmp(&b.centroid.component(axis))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    // Evaluates the SAH cost of every split position along the 3 axes,
+    // returns None when keeping a leaf is cheaper.
+    // A leaf too large to keep is split in two halves along the widest spread of the centroids instead,
+    // the SAH costs all being equal when the boxes coincide.
+    fn find_split(items: &mut [BuildItem], bounding_box: &BoundingBox) -> Option<(usize, usize)> {
+        let count = items.len();
+        if count <= 1 {
+            return None;
+        }
+        let parent_area = bounding_box.surface_area();
+        let mut best: Option<(usize, usize, f64)> = None;
+
+        for axis in 0..3 {
+            Self::sort_along(items, axis);
+
+            // right_areas[i] is the area of the box around items[i..]
+            let mut right_areas = vec![0.0; count];
+            let mut right_box = items[count - 1].bounding_box.clone();
+            for i in (1..count).rev() {
+                right_box = right_box.union(&items[i].bounding_box);
+                right_areas[i] = right_box.surface_area();
+            }
+
+            let mut left_box = items[0].bounding_box.clone();
+            for split in 1..count {
+                left_box = left_box.union(&items[split - 1].bounding_box);
+                let cost = TRAVERSAL_COST
+                    + if parent_area > 0.0 {
+                        (left_box.surface_area() * split as f64
+                            + right_areas[split] * (count - split) as f64)
+                            / parent_area
+                    } else {
+                        count as f64
+                    };
+                if best.as_ref().is_none_or(|(_, _, best_cost)| cost < *best_cost) {
+                    best = Some((axis, split, cost));
+                }
+            }
+        }
+
+        match best {
+            Some((axis, split, cost)) if cost < count as f64 => Some((axis, split)),
+            _ if count > MAX_THINGS_PER_LEAF => Some((Self::widest_centroid_axis(items), count / 2)),
+            _ => None,
+        }
+    }
+
+    // Walks the hierarchy and keeps the closest intersection returned by `intersect`,
+    // nodes further than the current closest intersection are skipped.
+    pub fn closest_intersection<H, F>(&self, ray: &Ray, mut intersect: F) -> Option<H>
+    where
+        H: Hit,
+        F: FnMut(usize) -> Option<H>,
+    {
+        let mut closest: Option<H> = None;
+        for thing_index in &self.unbounded {
+            keep_closest(intersect(*thing_index), &mut closest);
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let dir = ray.dir();
+        let inv_dir = Vector3d::new(1.0 / dir.x, 1.0 / dir.y, 1.0 / dir.z);
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let max_distance = closest.as_ref().map_or(f64::INFINITY, |c| c.distance());
+            if node
+                .bounding_box
+                .hit(ray.start(), &inv_dir, 0.0, max_distance)
+                .is_none()
+            {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    for thing_index in &self.thing_indices[first..first + count] {
+                        keep_closest(intersect(*thing_index), &mut closest);
+                    }
+                }
+                BvhNodeKind::Interior { left, right } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+        closest
+    }
+}
+
+fn keep_closest<H: Hit>(candidate: Option<H>, closest: &mut Option<H>) {
+    if let Some(candidate) = candidate {
+        if closest.as_ref().is_none_or(|c| candidate.distance() < c.distance()) {
+            *closest = Some(candidate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::BLACK, test_worlds::diffuse_surface, things::Sphere};
+
+    fn intersect(things: &[Box<dyn Thing>], thing_index: usize, ray: &Ray) -> Option<Intersection> {
+        things[thing_index]
+            .intersect(ray)
+            .into_iter()
+            .map(|hit| {
+                let distance = (ray.start() - &hit.position).mag();
+                Intersection::new(thing_index, hit, Vector3d::zero(), distance, true)
+            })
+            .filter(|i| (i.position() - ray.start()).dot(ray.dir()) > 0.0)
+            .min_by(|a, b| a.distance().partial_cmp(&b.distance()).unwrap())
+    }
+
+    #[test]
+    fn test_same_result_as_brute_force() {
+        let mut things: Vec<Box<dyn Thing>> = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                let position = Vector3d::new(10.0 + (i * j % 7) as f64, i as f64 - 5.0, j as f64 - 5.0);
+                let radius = 0.2 + 0.05 * ((i + j) % 5) as f64;
+                things.push(Box::new(Sphere::new(position, radius, diffuse_surface(BLACK))));
+            }
+        }
+        let bvh = Bvh::new(&things);
+        assert!(bvh.node_count() > 1);
+        assert!(bvh.unbounded().is_empty());
+
+        let start = Vector3d::zero();
+        for k in 0..400 {
+            let dir = Vector3d::new(1.0, (k % 20) as f64 * 0.05 - 0.5, (k / 20) as f64 * 0.05 - 0.5);
+            let ray = Ray::new(&start, &dir);
+            let expected = (0..things.len())
+                .filter_map(|index| intersect(&things, index, &ray))
+                .min_by(|a, b| a.distance().partial_cmp(&b.distance()).unwrap());
+            let found = bvh.closest_intersection(&ray, |index| intersect(&things, index, &ray));
+            assert_eq!(
+                expected.map(|i| i.thing_index()),
+                found.map(|i| i.thing_index())
+            );
+        }
+    }
+
+    fn depth(bvh: &Bvh, node_index: usize) -> usize {
+        match bvh.nodes[node_index].kind {
+            BvhNodeKind::Leaf { .. } => 1,
+            BvhNodeKind::Interior { left, right } => 1 + depth(bvh, left).max(depth(bvh, right)),
+        }
+    }
+
+    #[test]
+    fn test_coincident_boxes() {
+        let count = 60_000;
+        let bounding_box = BoundingBox::new(Vector3d::new(1.0, -1.0, -1.0), Vector3d::new(3.0, 1.0, 1.0));
+        let bvh = Bvh::from_bounding_boxes((0..count).map(|_| Some(bounding_box.clone())));
+        // Halved until the leaves are small enough
+        assert!(depth(&bvh, 0) <= 16);
+        let mut indices = bvh.thing_indices.clone();
+        indices.sort_unstable();
+        assert!(indices.into_iter().eq(0..count));
+
+        let ray = Ray::new(&Vector3d::zero(), &Vector3d::x_axis());
+        let mut tested = 0;
+        bvh.closest_intersection(&ray, |_| {
+            tested += 1;
+            None::<Intersection>
+        });
+        assert_eq!(tested, count);
+    }
+}
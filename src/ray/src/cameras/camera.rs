@@ -0,0 +1,138 @@
+use std::convert::TryFrom;
+
+use math::matrix4::Mat4;
+
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{error::CameraError, ray::Ray, sampling::Random, vector::Vector3d};
+
+// Projection from the image to the rays leaving the camera. Without a `type` the camera is
+// a perspective one.
+#[typetag::serde(tag = "type", default_variant = "perspective")]
+pub trait Camera: Send + Sync {
+    fn get_pixel_size(&self) -> (u16, u16);
+
+    // Fractional image coordinates, (0, 0) is the upper left corner of the first pixel.
+    // None when nothing is seen there, `random` is the random stream of the pixel.
+    fn get_ray(&self, pixel_x: f64, pixel_y: f64, random: &mut Random) -> Option<Ray>;
+}
+
+pub(crate) fn default_up_hint() -> Vector3d {
+    Vector3d::z_axis()
+}
+
+pub(crate) fn check_image_size(image_pixels_width: u16, image_pixels_height: u16) -> Result<(), CameraError> {
+    if image_pixels_width == 0 || image_pixels_height == 0 {
+        return Err(CameraError::EmptyImage);
+    }
+    Ok(())
+}
+
+// Width or height of the image in a scene file, refused like in `check_image_size` when 0.
+pub(crate) fn image_pixels<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pixels = u16::deserialize(deserializer)?;
+    check_image_size(pixels, pixels).map_err(D::Error::custom)?;
+    Ok(pixels)
+}
+
+#[derive(Serialize, Deserialize)]
+struct ViewSpec {
+    eye: Vector3d,
+    target: Vector3d,
+    // Only has to be roughly up, the true up is made perpendicular to the view direction
+    #[serde(default = "default_up_hint")]
+    up_hint: Vector3d,
+}
+
+// Camera at `eye` looking at `target`, with the orthonormal basis derived from them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "ViewSpec", into = "ViewSpec")]
+pub struct View {
+    eye: Vector3d,
+    target: Vector3d,
+    up_hint: Vector3d,
+    pub right: Vector3d,
+    pub up: Vector3d,
+    pub forward: Vector3d,
+}
+
+impl TryFrom<ViewSpec> for View {
+    type Error = CameraError;
+
+    fn try_from(spec: ViewSpec) -> Result<Self, Self::Error> {
+        Self::new(&spec.eye, &spec.target, &spec.up_hint)
+    }
+}
+
+impl From<View> for ViewSpec {
+    fn from(view: View) -> Self {
+        Self {
+            eye: view.eye,
+            target: view.target,
+            up_hint: view.up_hint,
+        }
+    }
+}
+
+impl View {
+    pub fn new(eye: &Vector3d, target: &Vector3d, up_hint: &Vector3d) -> Result<Self, CameraError> {
+        let basis = Mat4::look_at(eye, target, up_hint)?;
+        Ok(Self {
+            eye: eye.clone(),
+            target: target.clone(),
+            up_hint: up_hint.clone(),
+            right: basis.transform_vector(&Vector3d::x_axis()),
+            up: basis.transform_vector(&Vector3d::y_axis()),
+            forward: basis.transform_vector(&Vector3d::z_axis()),
+        })
+    }
+
+    pub fn eye(&self) -> &Vector3d {
+        &self.eye
+    }
+
+    // World direction of the camera space vector (x right, y up, z forward).
+    pub fn to_world(&self, x: f64, y: f64, z: f64) -> Vector3d {
+        &(&self.right.each_mul(x) + &self.up.each_mul(y)) + &self.forward.each_mul(z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_variant() {
+        let json = r#"{
+            "direction": { "x": 1.0, "y": 0.0, "z": 0.0 },
+            "up": { "x": 0.0, "y": 0.0, "z": 1.0 },
+            "right": { "x": 0.0, "y": -1.0, "z": 0.0 },
+            "position": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "focal_dist": 1.0,
+            "image_pixels_width": 40,
+            "image_pixels_height": 30,
+            "pixel_per_unit": 20.0,
+            "image_len_width": 2.0,
+            "image_len_height": 1.5
+        }"#;
+        let camera: Box<dyn Camera> = serde_json::from_str(json).unwrap();
+        assert_eq!(camera.get_pixel_size(), (40, 30));
+        let ray = camera.get_ray(20.0, 15.0, &mut Random::new(0)).unwrap();
+        assert!((ray.dir() - &Vector3d::x_axis()).mag() < 1e-12);
+
+        let typed = json.replacen('{', r#"{ "type": "perspective","#, 1);
+        assert!(serde_json::from_str::<Box<dyn Camera>>(&typed).is_ok());
+    }
+
+    #[test]
+    fn test_view() {
+        let view = View::new(&Vector3d::zero(), &Vector3d::new(0.0, 3.0, 0.0), &Vector3d::z_axis()).unwrap();
+        assert!((&view.to_world(1.0, 2.0, 3.0) - &Vector3d::new(1.0, 3.0, 2.0)).mag() < 1e-12);
+
+        let json = r#"{ "eye": { "x": 0.0, "y": 0.0, "z": 0.0 }, "target": { "x": 0.0, "y": 0.0, "z": -2.0 } }"#;
+        assert!(serde_json::from_str::<View>(json).is_err());
+    }
+}
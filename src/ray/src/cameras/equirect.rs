@@ -0,0 +1,73 @@
+use std::f64::consts::PI;
+
+use crate::{error::CameraError, ray::Ray, sampling::Random};
+
+use super::{check_image_size, image_pixels, Camera, View};
+
+// Full 360 x 180 degrees panorama: the longitude goes from -180 to 180 degrees across the
+// width of the image, the target being at the center, and the latitude from 90 degrees at
+// the top to -90 degrees at the bottom.
+#[derive(Serialize, Deserialize)]
+pub struct EquirectCamera {
+    #[serde(flatten)]
+    view: View,
+    #[serde(deserialize_with = "image_pixels")]
+    image_pixels_width: u16,
+    #[serde(deserialize_with = "image_pixels")]
+    image_pixels_height: u16,
+}
+
+impl EquirectCamera {
+    pub fn new(view: View, image_pixels_width: u16, image_pixels_height: u16) -> Result<Self, CameraError> {
+        check_image_size(image_pixels_width, image_pixels_height)?;
+        Ok(Self {
+            view,
+            image_pixels_width,
+            image_pixels_height,
+        })
+    }
+}
+
+#[typetag::serde(name = "equirect")]
+impl Camera for EquirectCamera {
+    fn get_pixel_size(&self) -> (u16, u16) {
+        (self.image_pixels_width, self.image_pixels_height)
+    }
+
+    fn get_ray(&self, pixel_x: f64, pixel_y: f64, _random: &mut Random) -> Option<Ray> {
+        let longitude = (pixel_x / self.image_pixels_width as f64 - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - pixel_y / self.image_pixels_height as f64) * PI;
+        let dir = self.view.to_world(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            latitude.cos() * longitude.cos(),
+        );
+        Some(Ray::new(self.view.eye(), &dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Vector3d;
+
+    #[test]
+    fn test_panorama() {
+        let json = r#"{
+            "type": "equirect",
+            "eye": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "target": { "x": 1.0, "y": 0.0, "z": 0.0 },
+            "image_pixels_width": 360,
+            "image_pixels_height": 180
+        }"#;
+        let camera: Box<dyn Camera> = serde_json::from_str(json).unwrap();
+        let mut random = Random::new(0);
+        let dir_at = |x: f64, y: f64, random: &mut Random| camera.get_ray(x, y, random).unwrap().dir().clone();
+        assert!((&dir_at(180.0, 90.0, &mut random) - &Vector3d::x_axis()).mag() < 1e-12);
+        // A quarter turn to the right, then behind the camera on both edges
+        assert!((&dir_at(270.0, 90.0, &mut random) + &Vector3d::y_axis()).mag() < 1e-12);
+        assert!((&dir_at(0.0, 90.0, &mut random) + &Vector3d::x_axis()).mag() < 1e-12);
+        assert!((&dir_at(360.0, 90.0, &mut random) + &Vector3d::x_axis()).mag() < 1e-12);
+        assert!((&dir_at(42.0, 0.0, &mut random) - &Vector3d::z_axis()).mag() < 1e-12);
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{error::CameraError, ray::Ray, sampling::Random};
+
+use super::{check_image_size, image_pixels, Camera, View};
+
+fn default_fov() -> f64 {
+    180.0
+}
+
+fn fisheye_fov<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let degrees = f64::deserialize(deserializer)?;
+    if degrees > 0.0 && degrees <= 360.0 {
+        Ok(degrees)
+    } else {
+        Err(D::Error::custom(format!("the fisheye field of view must be in ]0, 360], got {}", degrees)))
+    }
+}
+
+// How the angle to the view direction grows with the distance to the image center.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FisheyeProjection {
+    // Proportional to the distance
+    #[default]
+    Equidistant,
+    // Preserves the areas: the distance is proportional to sin(angle / 2)
+    Equisolid,
+}
+
+impl FisheyeProjection {
+    // Angle to the view direction at `radius` from the center, the edge of the image circle
+    // (radius 1) being at `max_angle`.
+    fn angle(&self, radius: f64, max_angle: f64) -> f64 {
+        match self {
+            FisheyeProjection::Equidistant => radius * max_angle,
+            FisheyeProjection::Equisolid => 2.0 * (radius * (max_angle / 2.0).sin()).asin(),
+        }
+    }
+}
+
+// Round image of `fov` degrees, the circle touching the shorter side of the image. Nothing is
+// seen outside of the circle.
+#[derive(Serialize, Deserialize)]
+pub struct FisheyeCamera {
+    #[serde(flatten)]
+    view: View,
+    #[serde(default = "default_fov", deserialize_with = "fisheye_fov")]
+    fov: f64,
+    #[serde(default)]
+    projection: FisheyeProjection,
+    #[serde(deserialize_with = "image_pixels")]
+    image_pixels_width: u16,
+    #[serde(deserialize_with = "image_pixels")]
+    image_pixels_height: u16,
+}
+
+impl FisheyeCamera {
+    pub fn new(view: View, image_pixels_width: u16, image_pixels_height: u16) -> Result<Self, CameraError> {
+        check_image_size(image_pixels_width, image_pixels_height)?;
+        Ok(Self {
+            view,
+            fov: default_fov(),
+            projection: FisheyeProjection::default(),
+            image_pixels_width,
+            image_pixels_height,
+        })
+    }
+
+    pub fn with_projection(self, fov: f64, projection: FisheyeProjection) -> Self {
+        Self {
+            fov,
+            projection,
+            ..self
+        }
+    }
+}
+
+#[typetag::serde(name = "fisheye")]
+impl Camera for FisheyeCamera {
+    fn get_pixel_size(&self) -> (u16, u16) {
+        (self.image_pixels_width, self.image_pixels_height)
+    }
+
+    fn get_ray(&self, pixel_x: f64, pixel_y: f64, _random: &mut Random) -> Option<Ray> {
+        let (width, height) = (self.image_pixels_width as f64, self.image_pixels_height as f64);
+        let circle_radius = width.min(height) / 2.0;
+        let x = (pixel_x - width / 2.0) / circle_radius;
+        let y = (height / 2.0 - pixel_y) / circle_radius;
+        let radius = (x * x + y * y).sqrt();
+        if radius > 1.0 {
+            return None;
+        }
+        let angle = self.projection.angle(radius, self.fov.to_radians() / 2.0);
+        let (sin, cos) = angle.sin_cos();
+        let dir = if radius > 0.0 {
+            self.view.to_world(x / radius * sin, y / radius * sin, cos)
+        } else {
+            self.view.forward.clone()
+        };
+        Some(Ray::new(self.view.eye(), &dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Vector3d;
+
+    fn fisheye(projection: FisheyeProjection) -> FisheyeCamera {
+        let view = View::new(&Vector3d::zero(), &Vector3d::x_axis(), &Vector3d::z_axis()).unwrap();
+        FisheyeCamera::new(view, 40, 20).unwrap().with_projection(180.0, projection)
+    }
+
+    #[test]
+    fn test_projections() {
+        let mut random = Random::new(0);
+        for projection in &[FisheyeProjection::Equidistant, FisheyeProjection::Equisolid] {
+            let camera = fisheye(*projection);
+            let center = camera.get_ray(20.0, 10.0, &mut random).unwrap();
+            assert!((center.dir() - &Vector3d::x_axis()).mag() < 1e-12);
+            // The top of the circle looks straight up with a 180 degrees field of view
+            let top = camera.get_ray(20.0, 0.0, &mut random).unwrap();
+            assert!((top.dir() - &Vector3d::z_axis()).mag() < 1e-12);
+            assert!(camera.get_ray(0.0, 10.0, &mut random).is_none());
+        }
+
+        // Half way to the edge
+        let equidistant = fisheye(FisheyeProjection::Equidistant).get_ray(20.0, 5.0, &mut random).unwrap();
+        assert!((equidistant.dir().z - 45f64.to_radians().sin()).abs() < 1e-12);
+        let equisolid = fisheye(FisheyeProjection::Equisolid).get_ray(20.0, 5.0, &mut random).unwrap();
+        let angle = 2.0 * (0.5 * 45f64.to_radians().sin()).asin();
+        assert!((equisolid.dir().z - angle.sin()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = r#"{
+            "type": "fisheye",
+            "eye": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "target": { "x": 1.0, "y": 0.0, "z": 0.0 },
+            "projection": "equisolid",
+            "image_pixels_width": 64,
+            "image_pixels_height": 64
+        }"#;
+        let camera: Box<dyn Camera> = serde_json::from_str(json).unwrap();
+        assert!(camera.get_ray(32.0, 32.0, &mut Random::new(0)).is_some());
+        assert!(camera.get_ray(0.0, 0.0, &mut Random::new(0)).is_none());
+
+        let too_wide = json.replace(r#""projection""#, r#""fov": 400.0, "projection""#);
+        assert!(serde_json::from_str::<Box<dyn Camera>>(&too_wide).is_err());
+    }
+}
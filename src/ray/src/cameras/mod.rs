@@ -0,0 +1,14 @@
+mod camera;
+pub use camera::*;
+
+mod perspective;
+pub use perspective::*;
+
+mod orthographic;
+pub use orthographic::*;
+
+mod fisheye;
+pub use fisheye::*;
+
+mod equirect;
+pub use equirect::*;
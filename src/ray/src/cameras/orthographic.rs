@@ -0,0 +1,106 @@
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{error::CameraError, ray::Ray, sampling::Random};
+
+use super::{check_image_size, image_pixels, Camera, View};
+
+fn positive<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = f64::deserialize(deserializer)?;
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(D::Error::custom(format!("expected a strictly positive size, got {}", value)))
+    }
+}
+
+// Parallel rays along the view direction, without perspective: `view_height` world units
+// are seen across the height of the image.
+#[derive(Serialize, Deserialize)]
+pub struct OrthographicCamera {
+    #[serde(flatten)]
+    view: View,
+    #[serde(deserialize_with = "positive")]
+    view_height: f64,
+    #[serde(deserialize_with = "image_pixels")]
+    image_pixels_width: u16,
+    #[serde(deserialize_with = "image_pixels")]
+    image_pixels_height: u16,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        view: View,
+        view_height: f64,
+        image_pixels_width: u16,
+        image_pixels_height: u16,
+    ) -> Result<Self, CameraError> {
+        check_image_size(image_pixels_width, image_pixels_height)?;
+        Ok(Self {
+            view,
+            view_height,
+            image_pixels_width,
+            image_pixels_height,
+        })
+    }
+}
+
+#[typetag::serde(name = "orthographic")]
+impl Camera for OrthographicCamera {
+    fn get_pixel_size(&self) -> (u16, u16) {
+        (self.image_pixels_width, self.image_pixels_height)
+    }
+
+    // The rays start on the plane going through the eye.
+    fn get_ray(&self, pixel_x: f64, pixel_y: f64, _random: &mut Random) -> Option<Ray> {
+        let pixel_size = self.view_height / self.image_pixels_height as f64;
+        let x = (pixel_x - self.image_pixels_width as f64 / 2.0) * pixel_size;
+        let y = (self.image_pixels_height as f64 / 2.0 - pixel_y) * pixel_size;
+        let start = self.view.eye() + &self.view.to_world(x, y, 0.0);
+        Some(Ray::new(&start, &self.view.forward))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Vector3d;
+
+    #[test]
+    fn test_parallel_rays() {
+        let view = View::new(&Vector3d::zero(), &Vector3d::x_axis(), &Vector3d::z_axis()).unwrap();
+        let camera = OrthographicCamera::new(view, 2.0, 40, 20).unwrap();
+        let mut random = Random::new(0);
+        let corner = camera.get_ray(0.0, 0.0, &mut random).unwrap();
+        let center = camera.get_ray(20.0, 10.0, &mut random).unwrap();
+        assert_eq!(corner.dir(), center.dir());
+        assert!((center.start() - &Vector3d::zero()).mag() < 1e-12);
+        // Square pixels, the image is twice as wide as high
+        assert!((corner.start() - &Vector3d::new(0.0, 2.0, 1.0)).mag() < 1e-12);
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = r#"{
+            "type": "orthographic",
+            "eye": { "x": 0.0, "y": 0.0, "z": 5.0 },
+            "target": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "up_hint": { "x": 1.0, "y": 0.0, "z": 0.0 },
+            "view_height": 4.0,
+            "image_pixels_width": 64,
+            "image_pixels_height": 64
+        }"#;
+        let camera: Box<dyn Camera> = serde_json::from_str(json).unwrap();
+        let ray = camera.get_ray(32.0, 32.0, &mut Random::new(0)).unwrap();
+        assert!((ray.dir() + &Vector3d::z_axis()).mag() < 1e-12);
+        let round_trip = serde_json::to_string(&camera).unwrap();
+        assert!(serde_json::from_str::<Box<dyn Camera>>(&round_trip).is_ok());
+
+        let flat = json.replace("4.0", "0.0");
+        assert!(serde_json::from_str::<Box<dyn Camera>>(&flat).is_err());
+        let empty = json.replace(r#""image_pixels_width": 64"#, r#""image_pixels_width": 0"#);
+        assert!(serde_json::from_str::<Box<dyn Camera>>(&empty).is_err());
+    }
+}
@@ -0,0 +1,548 @@
+
+use std::{convert::TryFrom, fmt};
+
+use serde::{
+    de::{value::MapAccessDeserializer, Error, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    error::CameraError,
+    ray::Ray,
+    sampling::{uniform_disk, uniform_polygon, Random},
+    vector::Vector3d,
+};
+
+use super::{check_image_size, default_up_hint, Camera, View};
+
+// Allowed error on the lengths and dot products of the explicit basis.
+const BASIS_TOLERANCE: f64 = 1e-6;
+
+// Angle of view in degrees, across the height or the width of the image.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldOfView {
+    Vertical(f64),
+    Horizontal(f64),
+}
+
+// Thin lens giving depth of field: the rays start from random points of the lens and go
+// through the point seen by the pinhole camera at `focus_distance` along the view direction.
+// The lens is round or, with `blades`, a regular polygon turned by `rotation` degrees.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lens {
+    // Diameter of the lens
+    aperture: f64,
+    focus_distance: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blades: Option<u32>,
+    #[serde(default)]
+    rotation: f64,
+}
+
+impl Lens {
+    pub fn new(aperture: f64, focus_distance: f64) -> Self {
+        Self {
+            aperture,
+            focus_distance,
+            blades: None,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn with_blades(self, blades: u32, rotation: f64) -> Self {
+        Self {
+            blades: Some(blades),
+            rotation,
+            ..self
+        }
+    }
+
+    fn check(&self) -> Result<(), CameraError> {
+        if self.aperture.is_nan() || self.aperture < 0.0 {
+            return Err(CameraError::InvalidLens("the aperture must be positive"));
+        }
+        if self.focus_distance.is_nan() || self.focus_distance <= 0.0 {
+            return Err(CameraError::InvalidLens("the focus distance must be strictly positive"));
+        }
+        if self.blades.is_some_and(|blades| blades < 3) {
+            return Err(CameraError::InvalidLens("a polygonal lens needs at least 3 blades"));
+        }
+        Ok(())
+    }
+
+    // Random point of the lens in the (right, up) lens plane.
+    fn sample(&self, random: &mut Random) -> (f64, f64) {
+        let (x, y) = match self.blades {
+            Some(blades) => uniform_polygon(blades, self.rotation.to_radians(), random),
+            None => uniform_disk(random),
+        };
+        let radius = self.aperture / 2.0;
+        (x * radius, y * radius)
+    }
+}
+
+// Every field of the two forms, read before knowing which form is used.
+#[derive(Deserialize)]
+struct PerspectiveFields {
+    eye: Option<Vector3d>,
+    target: Option<Vector3d>,
+    #[serde(default = "default_up_hint")]
+    up_hint: Vector3d,
+    fov: Option<FieldOfView>,
+    direction: Option<Vector3d>,
+    up: Option<Vector3d>,
+    right: Option<Vector3d>,
+    position: Option<Vector3d>,
+    focal_dist: Option<f64>,
+    pixel_per_unit: Option<f64>,
+    image_len_width: Option<f64>,
+    image_len_height: Option<f64>,
+    image_pixels_width: Option<u16>,
+    image_pixels_height: Option<u16>,
+    #[serde(default)]
+    lens: Option<Lens>,
+}
+
+struct LookAtSpec {
+    view: View,
+    fov: FieldOfView,
+}
+
+struct ExplicitSpec {
+    direction: Vector3d,
+    up: Vector3d,
+    right: Vector3d,
+    position: Vector3d,
+    focal_dist: f64,
+    pixel_per_unit: f64,
+    image_len_width: f64,
+    image_len_height: f64,
+}
+
+enum PerspectiveForm {
+    LookAt(LookAtSpec),
+    Explicit(ExplicitSpec),
+}
+
+// The two ways to write a camera in a scene file: looking from `eye` at `target`, or with
+// the explicit basis and image plane.
+struct PerspectiveSpec {
+    form: PerspectiveForm,
+    image_pixels_width: u16,
+    image_pixels_height: u16,
+    lens: Option<Lens>,
+}
+
+impl<'de> Deserialize<'de> for PerspectiveSpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(PerspectiveVisitor)
+    }
+}
+
+struct PerspectiveVisitor;
+
+impl<'de> Visitor<'de> for PerspectiveVisitor {
+    type Value = PerspectiveSpec;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a look at or an explicit perspective camera")
+    }
+
+    // The form is chosen by the presence of `eye`, the missing fields are then the ones of that form.
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        fn required<T, E: Error>(value: Option<T>, name: &'static str) -> Result<T, E> {
+            value.ok_or_else(|| E::missing_field(name))
+        }
+
+        let fields = PerspectiveFields::deserialize(MapAccessDeserializer::new(map))?;
+        let form = match fields.eye {
+            Some(eye) => {
+                let target = required(fields.target, "target")?;
+                PerspectiveForm::LookAt(LookAtSpec {
+                    view: View::new(&eye, &target, &fields.up_hint).map_err(A::Error::custom)?,
+                    fov: required(fields.fov, "fov")?,
+                })
+            }
+            None => PerspectiveForm::Explicit(ExplicitSpec {
+                direction: required(fields.direction, "direction")?,
+                up: required(fields.up, "up")?,
+                right: required(fields.right, "right")?,
+                position: required(fields.position, "position")?,
+                focal_dist: required(fields.focal_dist, "focal_dist")?,
+                pixel_per_unit: required(fields.pixel_per_unit, "pixel_per_unit")?,
+                image_len_width: required(fields.image_len_width, "image_len_width")?,
+                image_len_height: required(fields.image_len_height, "image_len_height")?,
+            }),
+        };
+        Ok(PerspectiveSpec {
+            form,
+            image_pixels_width: required(fields.image_pixels_width, "image_pixels_width")?,
+            image_pixels_height: required(fields.image_pixels_height, "image_pixels_height")?,
+            lens: fields.lens,
+        })
+    }
+}
+
+impl TryFrom<PerspectiveSpec> for PerspectiveCamera {
+    type Error = CameraError;
+
+    fn try_from(spec: PerspectiveSpec) -> Result<Self, Self::Error> {
+        let (width, height) = (spec.image_pixels_width, spec.image_pixels_height);
+        let camera = match spec.form {
+            PerspectiveForm::LookAt(form) => PerspectiveCamera::from_view(&form.view, form.fov, width, height)?,
+            PerspectiveForm::Explicit(form) => {
+                let camera = PerspectiveCamera {
+                    direction: form.direction,
+                    up: form.up,
+                    right: form.right,
+                    position: form.position,
+                    focal_dist: form.focal_dist,
+                    image_pixels_width: width,
+                    image_pixels_height: height,
+                    pixel_per_unit: form.pixel_per_unit,
+                    image_len_width: form.image_len_width,
+                    image_len_height: form.image_len_height,
+                    lens: None,
+                };
+                camera.check()?;
+                camera
+            }
+        };
+        match spec.lens {
+            Some(lens) => camera.with_lens(lens),
+            None => Ok(camera),
+        }
+    }
+}
+
+// Always written back in the explicit form.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "PerspectiveSpec")]
+pub struct PerspectiveCamera {
+    direction: Vector3d,
+    up: Vector3d,
+    right: Vector3d,
+    position: Vector3d,
+    focal_dist: f64,
+    image_pixels_width: u16,
+    image_pixels_height: u16,
+    pixel_per_unit: f64,
+    image_len_width: f64,
+    image_len_height: f64,
+    // Pinhole camera, everything in focus, without a lens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lens: Option<Lens>,
+}
+
+impl Default for PerspectiveCamera {
+    fn default() -> Self {
+        let direction = Vector3d::x_axis();
+        let right = -Vector3d::y_axis();
+        let up = Vector3d::z_axis();
+        let position = Vector3d::zero();
+        let focal_dist = 2.0;
+        let image_pixels_width = 640;
+        let image_pixels_height = 480;
+        let pixel_per_unit = 320.0;
+        let image_len_width = Self::compute_image_size(image_pixels_width, pixel_per_unit);
+        let image_len_height = Self::compute_image_size(image_pixels_height, pixel_per_unit);
+        Self {
+            direction,
+            right,
+            up,
+            position,
+            focal_dist,
+            image_pixels_width,
+            image_pixels_height,
+            pixel_per_unit,
+            image_len_width,
+            image_len_height,
+            lens: None,
+        }
+    }
+}
+impl PerspectiveCamera {
+    // Camera at `eye` looking at `target`, the image plane being one unit in front of the eye.
+    pub fn look_at(
+        eye: &Vector3d,
+        target: &Vector3d,
+        up_hint: &Vector3d,
+        fov: FieldOfView,
+        image_pixels_width: u16,
+        image_pixels_height: u16,
+    ) -> Result<Self, CameraError> {
+        let view = View::new(eye, target, up_hint)?;
+        Self::from_view(&view, fov, image_pixels_width, image_pixels_height)
+    }
+
+    pub fn from_view(
+        view: &View,
+        fov: FieldOfView,
+        image_pixels_width: u16,
+        image_pixels_height: u16,
+    ) -> Result<Self, CameraError> {
+        check_image_size(image_pixels_width, image_pixels_height)?;
+        let degrees = match fov {
+            FieldOfView::Vertical(degrees) | FieldOfView::Horizontal(degrees) => degrees,
+        };
+        if !(degrees > 0.0 && degrees < 180.0) {
+            return Err(CameraError::InvalidFov(degrees));
+        }
+        let direction = view.forward.clone();
+        let focal_dist = 1.0;
+        let aspect_ratio = image_pixels_width as f64 / image_pixels_height as f64;
+        let half_extent = focal_dist * (degrees.to_radians() / 2.0).tan();
+        let (image_len_width, image_len_height) = match fov {
+            FieldOfView::Vertical(_) => (2.0 * half_extent * aspect_ratio, 2.0 * half_extent),
+            FieldOfView::Horizontal(_) => (2.0 * half_extent, 2.0 * half_extent / aspect_ratio),
+        };
+        Ok(Self {
+            position: view.eye() + &(&direction * &focal_dist.into()),
+            direction,
+            up: view.up.clone(),
+            right: view.right.clone(),
+            focal_dist,
+            image_pixels_width,
+            image_pixels_height,
+            pixel_per_unit: image_pixels_width as f64 / image_len_width,
+            image_len_width,
+            image_len_height,
+            lens: None,
+        })
+    }
+
+    // The explicit form is taken as written, the basis has to be orthonormal.
+    fn check(&self) -> Result<(), CameraError> {
+        check_image_size(self.image_pixels_width, self.image_pixels_height)?;
+        // False for NaN as well
+        let positive = |value: f64| value > 0.0;
+        if !positive(self.focal_dist) {
+            return Err(CameraError::InvalidFocalDistance(self.focal_dist));
+        }
+        if ![self.pixel_per_unit, self.image_len_width, self.image_len_height].iter().all(|v| positive(*v)) {
+            return Err(CameraError::InvalidImagePlane);
+        }
+        let unit = |v: &Vector3d| (v.mag() - 1.0).abs() < BASIS_TOLERANCE;
+        let orthogonal = |a: &Vector3d, b: &Vector3d| a.dot(b).abs() < BASIS_TOLERANCE;
+        let orthonormal = unit(&self.direction)
+            && unit(&self.up)
+            && unit(&self.right)
+            && orthogonal(&self.right, &self.up)
+            && orthogonal(&self.right, &self.direction)
+            && orthogonal(&self.up, &self.direction);
+        if !orthonormal {
+            return Err(CameraError::InvalidBasis);
+        }
+        Ok(())
+    }
+
+    pub fn with_lens(self, lens: Lens) -> Result<Self, CameraError> {
+        lens.check()?;
+        Ok(Self {
+            lens: Some(lens),
+            ..self
+        })
+    }
+
+    fn compute_image_size(pixels: u16, pixels_per_unit: f64) -> f64 {
+        pixels as f64 / pixels_per_unit
+    }
+
+    pub fn image_spot(&self) -> Vector3d {
+        self.position.clone() - (self.direction.clone() * self.focal_dist.into())
+    }
+
+    pub fn up_left(&self) -> Vector3d {
+        self.position.clone() 
+            + (self.up.clone() * self.image_len_height.into() / 2.0.into())
+            - (self.right.clone() * self.image_len_width.into() / 2.0.into())
+    }
+
+    // Ray of the pinhole camera, through the center of the lens.
+    pub fn get_ray_at(&self, pixel_x: f64, pixel_y: f64) -> Ray {
+        let upleft_position = self.up_left();
+
+        let x_increment = self.right.clone() * (self.image_len_width * pixel_x / self.image_pixels_width as f64).into();
+        let y_increment = -self.up.clone() * (self.image_len_height * pixel_y / self.image_pixels_height as f64).into();
+
+        let point_on_screen = upleft_position + x_increment + y_increment;
+
+        let dir = point_on_screen.clone() - self.image_spot();
+        let start = self.image_spot().clone();
+        Ray::new(&start, &dir)
+    }
+}
+
+#[typetag::serde(name = "perspective")]
+impl Camera for PerspectiveCamera {
+    fn get_pixel_size(&self) -> (u16, u16) {
+        (self.image_pixels_width, self.image_pixels_height)
+    }
+
+    // Through the lens when there is one, the random stream is left untouched otherwise.
+    fn get_ray(&self, pixel_x: f64, pixel_y: f64, random: &mut Random) -> Option<Ray> {
+        let pinhole = self.get_ray_at(pixel_x, pixel_y);
+        let lens = match &self.lens {
+            Some(lens) if lens.aperture > 0.0 => lens,
+            _ => return Some(pinhole),
+        };
+        // The focus plane is perpendicular to the view direction
+        let distance = lens.focus_distance / pinhole.dir().dot(&self.direction);
+        let focus_point = pinhole.start() + &pinhole.dir().each_mul(distance);
+        let (x, y) = lens.sample(random);
+        let start = &(pinhole.start() + &self.right.each_mul(x)) + &self.up.each_mul(y);
+        Some(Ray::new(&start, &(&focus_point - &start)))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_spot() {
+        // The eye is behind the image plane, at the focal distance
+        let camera = PerspectiveCamera::default();
+        assert_eq!(camera.image_spot(), Vector3d::new(-2.0, 0.0, 0.0));
+        assert_eq!(camera.up_left(), Vector3d::new(0.0, 1.0, 0.75));
+    }
+
+    #[test]
+    fn test_ray() {
+        let camera = PerspectiveCamera::default();
+        let ray = camera.get_ray_at(0.0, 0.0);
+        assert_eq!(ray.start(), &Vector3d::new(-2.0, 0.0, 0.0));
+        assert!((ray.dir() - &Vector3d::new(2.0, 1.0, 0.75).norm().unwrap()).mag() < 1e-12);
+    }
+
+    #[test]
+    fn test_look_at() {
+        let eye = Vector3d::new(0.0, 0.0, 0.0);
+        let target = Vector3d::new(5.0, 0.0, 0.0);
+        let look_at = |fov| PerspectiveCamera::look_at(&eye, &target, &Vector3d::z_axis(), fov, 200, 100).unwrap();
+        let camera = look_at(FieldOfView::Vertical(90.0));
+        assert!((&camera.image_spot() - &eye).mag() < 1e-12);
+        let ray = camera.get_ray_at(100.0, 50.0);
+        assert!((ray.dir() - &Vector3d::x_axis()).mag() < 1e-12);
+        // The top of the image is 45 degrees up, the right edge at atan(2) to the right
+        let top = camera.get_ray_at(100.0, 0.0);
+        assert!((top.dir() - &Vector3d::new(1.0, 0.0, 1.0).norm().unwrap()).mag() < 1e-12);
+        let right = camera.get_ray_at(200.0, 50.0);
+        assert!((right.dir() - &Vector3d::new(1.0, -2.0, 0.0).norm().unwrap()).mag() < 1e-12);
+
+        let wide = look_at(FieldOfView::Horizontal(90.0));
+        let right = wide.get_ray_at(200.0, 50.0);
+        assert!((right.dir() - &Vector3d::new(1.0, -1.0, 0.0).norm().unwrap()).mag() < 1e-12);
+    }
+
+    #[test]
+    fn test_invalid_look_at() {
+        let eye = Vector3d::zero();
+        let up = Vector3d::z_axis();
+        let fov = FieldOfView::Vertical(60.0);
+        assert!(PerspectiveCamera::look_at(&eye, &eye, &up, fov, 10, 10).is_err());
+        assert!(PerspectiveCamera::look_at(&eye, &Vector3d::new(0.0, 0.0, 3.0), &up, fov, 10, 10).is_err());
+        let flat = FieldOfView::Horizontal(180.0);
+        assert!(PerspectiveCamera::look_at(&eye, &Vector3d::x_axis(), &up, flat, 10, 10).is_err());
+        assert!(PerspectiveCamera::look_at(&eye, &Vector3d::x_axis(), &up, fov, 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        let look_at = r#"{
+            "eye": { "x": 1.0, "y": 2.0, "z": 0.0 },
+            "target": { "x": 1.0, "y": 5.0, "z": 0.0 },
+            "fov": { "horizontal": 60.0 },
+            "image_pixels_width": 320,
+            "image_pixels_height": 240
+        }"#;
+        let camera: PerspectiveCamera = serde_json::from_str(look_at).unwrap();
+        assert!((&camera.direction - &Vector3d::y_axis()).mag() < 1e-12);
+        assert!((&camera.up - &Vector3d::z_axis()).mag() < 1e-12);
+        assert_eq!(camera.get_pixel_size(), (320, 240));
+
+        // Written back in the explicit form
+        let explicit = serde_json::to_string(&camera).unwrap();
+        let back: PerspectiveCamera = serde_json::from_str(&explicit).unwrap();
+        assert!((back.get_ray_at(12.0, 34.0).dir() - camera.get_ray_at(12.0, 34.0).dir()).mag() < 1e-12);
+
+        let degenerate = look_at.replace(r#""y": 5.0"#, r#""y": 2.0"#);
+        assert!(serde_json::from_str::<PerspectiveCamera>(&degenerate).is_err());
+
+        let with_lens = look_at.replace(r#""fov""#, r#""lens": { "aperture": 0.2, "focus_distance": 3.0 }, "fov""#);
+        let camera: PerspectiveCamera = serde_json::from_str(&with_lens).unwrap();
+        assert_eq!(camera.lens, Some(Lens::new(0.2, 3.0)));
+        let round_trip: PerspectiveCamera = serde_json::from_str(&serde_json::to_string(&camera).unwrap()).unwrap();
+        assert_eq!(round_trip.lens, camera.lens);
+        let no_blades = with_lens.replace("3.0 }", "3.0, \"blades\": 1 }");
+        assert!(serde_json::from_str::<PerspectiveCamera>(&no_blades).is_err());
+    }
+
+    #[test]
+    fn test_explicit() {
+        let camera = serde_json::to_string(&PerspectiveCamera::default()).unwrap();
+        assert!(serde_json::from_str::<PerspectiveCamera>(&camera).is_ok());
+        let invalid = |from: &str, to: &str| serde_json::from_str::<PerspectiveCamera>(&camera.replacen(from, to, 1));
+        assert!(invalid(r#""focal_dist":2.0"#, r#""focal_dist":0.0"#).is_err());
+        assert!(invalid(r#""image_pixels_width":640"#, r#""image_pixels_width":0"#).is_err());
+        assert!(invalid(r#""pixel_per_unit":320.0"#, r#""pixel_per_unit":-1.0"#).is_err());
+        // Up leaning to the right, then twice too long
+        assert!(invalid(r#""up":{"x":0.0,"y":0.0,"z":1.0}"#, r#""up":{"x":0.0,"y":-0.6,"z":0.8}"#).is_err());
+        assert!(invalid(r#""up":{"x":0.0,"y":0.0,"z":1.0}"#, r#""up":{"x":0.0,"y":0.0,"z":2.0}"#).is_err());
+
+        // The errors are the ones of the form chosen by the presence of `eye`
+        let look_at = r#"{ "eye": { "x": 0.0, "y": 0.0, "z": 0.0 }, "target": { "x": 1.0, "y": 0.0, "z": 0.0 } }"#;
+        let error = serde_json::from_str::<PerspectiveCamera>(look_at).unwrap_err().to_string();
+        assert!(error.contains("missing field `fov`"), "{}", error);
+        let error = serde_json::from_str::<PerspectiveCamera>("{}").unwrap_err().to_string();
+        assert!(error.contains("missing field `direction`"), "{}", error);
+    }
+
+    #[test]
+    fn test_lens() {
+        let eye = Vector3d::zero();
+        let target = Vector3d::x_axis();
+        let fov = FieldOfView::Vertical(60.0);
+        let camera = PerspectiveCamera::look_at(&eye, &target, &Vector3d::z_axis(), fov, 64, 48).unwrap();
+        let lens = Lens::new(0.5, 4.0).with_blades(6, 30.0);
+        let blurred = camera.clone().with_lens(lens).unwrap();
+        let mut random = Random::new(1);
+        for _ in 0..100 {
+            let pinhole = camera.get_ray_at(10.0, 40.0);
+            let ray = blurred.get_ray(10.0, 40.0, &mut random).unwrap();
+            // Every ray starts on the lens and goes through the point in focus
+            assert!(ray.start().x.abs() < 1e-12 && ray.start().mag() <= 0.25 + 1e-12);
+            let to_focus = 4.0 / ray.dir().x;
+            let focus_point = ray.start() + &ray.dir().each_mul(to_focus);
+            let expected = pinhole.start() + &pinhole.dir().each_mul(4.0 / pinhole.dir().x);
+            assert!((&focus_point - &expected).mag() < 1e-9);
+        }
+
+        // Without a lens or aperture the rays are the pinhole ones
+        let mut random = Random::new(1);
+        let pinhole = camera.get_ray(10.0, 40.0, &mut random).unwrap();
+        assert_eq!(pinhole.dir(), camera.get_ray_at(10.0, 40.0).dir());
+        assert_eq!(random.next_u64(), Random::new(1).next_u64());
+
+        assert!(camera.clone().with_lens(Lens::new(-1.0, 4.0)).is_err());
+        assert!(camera.clone().with_lens(Lens::new(0.1, 0.0)).is_err());
+        assert!(camera.with_lens(Lens::new(0.1, 4.0).with_blades(2, 0.0)).is_err());
+    }
+
+    #[test]
+    fn test_fractional_ray() {
+        let camera = PerspectiveCamera::default();
+        let (width, height) = camera.get_pixel_size();
+        // The center of the image is straight ahead
+        let ray = camera.get_ray_at(width as f64 / 2.0, height as f64 / 2.0);
+        assert!((ray.dir() - &Vector3d::x_axis()).mag() < 1e-12);
+        let ray = camera.get_ray(3.0, 4.0, &mut Random::new(0)).unwrap();
+        assert_eq!(ray.dir(), camera.get_ray_at(3.0, 4.0).dir());
+    }
+}
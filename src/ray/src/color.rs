@@ -2,7 +2,7 @@ use std::{iter::Sum, ops::{Add, Mul}};
 
 pub const WHITE: Color = Color{r: 1.0, g: 1.0, b:1.0};
 pub const BLACK: Color = Color{r: 0.0, g: 0.0, b:0.0};
-#[derive(Clone,Serialize,Deserialize)]
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
 pub struct Color {
     pub r: f64,
     pub g: f64,
@@ -44,9 +44,26 @@ impl Color {
         }
     }
 
+    pub fn average(&self) -> f64 {
+        (self.r + self.g + self.b) / 3.0
+    }
+
+    pub fn max_component(&self) -> f64 {
+        self.r.max(self.g).max(self.b)
+    }
+
     pub fn is_black(&self) -> bool {
         self.r == 0.0 && self.g == 0.0 && self.b == 0.0
     }
+
+    // Linear interpolation, `self` for t = 0 and `other` for t = 1.
+    pub fn lerp(&self, other: &Color, t: f64) -> Color {
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
 }
 
 impl Add for Color {
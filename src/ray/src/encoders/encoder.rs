@@ -0,0 +1,68 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{error::ImageError, image::Image};
+
+use super::{ExrCompression, ExrEncoder, ExrPrecision, HdrEncoder, PngEncoder, PpmEncoder};
+
+pub trait ImageEncoder {
+    fn encode(&self, image: &Image, writer: &mut dyn Write) -> Result<(), ImageError>;
+
+    // High dynamic range formats are given the linear rendered image, the other ones the
+    // tone mapped image.
+    fn is_linear(&self) -> bool {
+        false
+    }
+}
+
+// Settings of the formats that have some, each encoder only reads its own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EncoderOptions {
+    // ppm in ascii (P3) instead of binary (P6)
+    pub ascii: bool,
+    pub exr_precision: ExrPrecision,
+    pub exr_compression: ExrCompression,
+}
+
+// The encoder is chosen from the file extension.
+pub fn encoder_for_path(path: &Path, options: &EncoderOptions) -> Result<Box<dyn ImageEncoder>, ImageError> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => Ok(Box::new(PngEncoder::new())),
+        "ppm" => Ok(Box::new(PpmEncoder::new(options.ascii))),
+        "hdr" => Ok(Box::new(HdrEncoder::new())),
+        "exr" => Ok(Box::new(ExrEncoder::new(options.exr_precision, options.exr_compression))),
+        _ => Err(ImageError::UnsupportedFormat(path.display().to_string())),
+    }
+}
+
+pub fn save_image(image: &Image, path: &Path, encoder: &dyn ImageEncoder) -> Result<(), ImageError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    encoder.encode(image, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoder_for_path() {
+        let options = EncoderOptions::default();
+        assert!(!encoder_for_path(Path::new("out.png"), &options).unwrap().is_linear());
+        assert!(encoder_for_path(Path::new("out.PPM"), &options).is_ok());
+        assert!(encoder_for_path(Path::new("out.hdr"), &options).unwrap().is_linear());
+        assert!(encoder_for_path(Path::new("out.exr"), &options).unwrap().is_linear());
+        assert!(encoder_for_path(Path::new("out.jpg"), &options).is_err());
+        assert!(encoder_for_path(Path::new("out"), &options).is_err());
+    }
+}
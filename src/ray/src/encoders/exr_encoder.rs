@@ -0,0 +1,192 @@
+use std::{
+    io::{Cursor, Write},
+    str::FromStr,
+};
+
+use exr::prelude::{
+    f16, Blocks, Compression, Encoding, Image as ExrImage, IntoSample, Layer, LayerAttributes, LineOrder,
+    SpecificChannels, Vec2, WritableImage,
+};
+
+use crate::{error::ImageError, image::Image};
+
+use super::ImageEncoder;
+
+// Size of the samples stored in the file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExrPrecision {
+    // 16 bits floats
+    #[default]
+    Half,
+    // 32 bits floats
+    Float,
+}
+
+impl ExrPrecision {
+    pub const NAMES: [&'static str; 2] = ["half", "float"];
+}
+
+impl FromStr for ExrPrecision {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "half" => Ok(ExrPrecision::Half),
+            "float" => Ok(ExrPrecision::Float),
+            _ => Err(format!("Unknown OpenEXR precision: {}", name)),
+        }
+    }
+}
+
+// Lossless compressions of the scanlines.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExrCompression {
+    None,
+    // Deflate on blocks of 16 scanlines
+    #[default]
+    Zip,
+    // Wavelet and Huffman coding, best for noisy images
+    Piz,
+}
+
+impl ExrCompression {
+    pub const NAMES: [&'static str; 3] = ["none", "zip", "piz"];
+
+    fn compression(&self) -> Compression {
+        match self {
+            ExrCompression::None => Compression::Uncompressed,
+            ExrCompression::Zip => Compression::ZIP16,
+            ExrCompression::Piz => Compression::PIZ,
+        }
+    }
+}
+
+impl FromStr for ExrCompression {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "none" => Ok(ExrCompression::None),
+            "zip" => Ok(ExrCompression::Zip),
+            "piz" => Ok(ExrCompression::Piz),
+            _ => Err(format!("Unknown OpenEXR compression: {}", name)),
+        }
+    }
+}
+
+// OpenEXR scanline file with the R, G and B channels, keeping the linear radiance.
+#[derive(Default)]
+pub struct ExrEncoder {
+    precision: ExrPrecision,
+    compression: ExrCompression,
+}
+
+impl ExrEncoder {
+    pub fn new(precision: ExrPrecision, compression: ExrCompression) -> Self {
+        Self { precision, compression }
+    }
+
+    fn write<S, F>(&self, image: &Image, sample: F, out: &mut Cursor<Vec<u8>>) -> Result<(), ImageError>
+    where
+        S: IntoSample,
+        F: Fn(f64) -> S + Sync,
+    {
+        let encoding = Encoding {
+            compression: self.compression.compression(),
+            blocks: Blocks::ScanLines,
+            line_order: LineOrder::Increasing,
+        };
+        let channels = SpecificChannels::rgb(|position: Vec2<usize>| {
+            let color = image.get_color(position.x() as u16, position.y() as u16);
+            (sample(color.r), sample(color.g), sample(color.b))
+        });
+        let size = (image.width() as usize, image.height() as usize);
+        let layer = Layer::new(size, LayerAttributes::default(), encoding, channels);
+        ExrImage::from_layer(layer).write().to_buffered(out)?;
+        Ok(())
+    }
+}
+
+impl ImageEncoder for ExrEncoder {
+    fn encode(&self, image: &Image, writer: &mut dyn Write) -> Result<(), ImageError> {
+        // The offset table at the start of the file is written last, which needs seeking
+        let mut buffer = Cursor::new(Vec::new());
+        match self.precision {
+            ExrPrecision::Half => self.write(image, f16::from_f64, &mut buffer)?,
+            ExrPrecision::Float => self.write(image, |value| value as f32, &mut buffer)?,
+        }
+        writer.write_all(buffer.get_ref())?;
+        Ok(())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use exr::prelude::{read, ReadChannels, ReadLayers};
+
+    fn gradient() -> Image {
+        let mut image = Image::new(37, 21, Color::default());
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                image.set_color(x, y, Color::new(x as f64 * 0.5, y as f64 / 21.0, 1000.0));
+            }
+        }
+        image
+    }
+
+    fn read_back(data: Vec<u8>) -> Image {
+        let image = read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .rgba_channels(
+                |resolution, _| Image::new(resolution.width() as u16, resolution.height() as u16, Color::default()),
+                |image: &mut Image, position, (r, g, b, _): (f32, f32, f32, f32)| {
+                    image.set_color(position.x() as u16, position.y() as u16, Color::new(r as f64, g as f64, b as f64))
+                },
+            )
+            .first_valid_layer()
+            .all_attributes()
+            .from_buffered(Cursor::new(data))
+            .unwrap();
+        image.layer_data.channel_data.pixels
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let image = gradient();
+        for precision in &[ExrPrecision::Half, ExrPrecision::Float] {
+            for compression in &[ExrCompression::None, ExrCompression::Zip, ExrCompression::Piz] {
+                let mut data: Vec<u8> = Vec::new();
+                ExrEncoder::new(*precision, *compression).encode(&image, &mut data).unwrap();
+                let back = read_back(data);
+                assert_eq!((back.width(), back.height()), (image.width(), image.height()));
+                let tolerance = if *precision == ExrPrecision::Half { 1e-3 } else { 1e-6 };
+                for y in 0..image.height() {
+                    for x in 0..image.width() {
+                        let (expected, color) = (image.get_color(x, y), back.get_color(x, y));
+                        for (a, b) in [(expected.r, color.r), (expected.g, color.g), (expected.b, color.b)] {
+                            assert!((a - b).abs() <= tolerance * a.abs().max(1.0));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        for name in ExrPrecision::NAMES.iter() {
+            assert!(name.parse::<ExrPrecision>().is_ok());
+        }
+        for name in ExrCompression::NAMES.iter() {
+            assert!(name.parse::<ExrCompression>().is_ok());
+        }
+        assert!("b44".parse::<ExrCompression>().is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use std::io::Write;
+
+use crate::{color::Color, error::ImageError, image::Image};
+
+use super::ImageEncoder;
+
+// Shared exponent encoding: the three mantissas are scaled by the exponent of the largest
+// channel. Negative values cannot be stored and become 0.
+fn to_rgbe(color: &Color) -> [u8; 4] {
+    let channels = [color.r, color.g, color.b].map(|value| if value > 0.0 { value } else { 0.0 });
+    let max = channels[0].max(channels[1]).max(channels[2]);
+    if !max.is_finite() || max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // max = mantissa * 2^exponent, the mantissa being in [0.5, 1)
+    let mut exponent = max.log2().floor() as i32 + 1;
+    let mantissa = max / 2f64.powi(exponent);
+    if mantissa >= 1.0 {
+        exponent += 1;
+    } else if mantissa < 0.5 {
+        exponent -= 1;
+    }
+    if exponent > 127 {
+        return [255, 255, 255, 255];
+    }
+    let scale = 256.0 / 2f64.powi(exponent);
+    let [r, g, b] = channels.map(|value| (value * scale).min(255.0) as u8);
+    [r, g, b, (exponent + 128).max(0) as u8]
+}
+
+// Run length encoding of one component of a scanline: runs of equal bytes are stored as
+// (128 + length, byte) and the rest as (length, bytes...).
+fn write_rle(data: &[u8], out: &mut Vec<u8>) {
+    const MIN_RUN: usize = 3;
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && run < 127 && data[i + run] == data[i] {
+            run += 1;
+        }
+        if run >= MIN_RUN {
+            out.push(128 + run as u8);
+            out.push(data[i]);
+            i += run;
+            continue;
+        }
+        // Literal bytes up to the next run
+        let start = i;
+        while i < data.len() && i - start < 128 {
+            if i + MIN_RUN <= data.len() && data[i..i + MIN_RUN].iter().all(|byte| *byte == data[i]) {
+                break;
+            }
+            i += 1;
+        }
+        out.push((i - start) as u8);
+        out.extend_from_slice(&data[start..i]);
+    }
+}
+
+// Radiance RGBE (.hdr) file, keeping the linear radiance.
+// https://www.graphics.cornell.edu/~bjw/rgbe.html
+#[derive(Default)]
+pub struct HdrEncoder;
+
+impl HdrEncoder {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ImageEncoder for HdrEncoder {
+    fn encode(&self, image: &Image, writer: &mut dyn Write) -> Result<(), ImageError> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        write!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width)?;
+
+        // Only the scanlines from 8 to 32767 pixels can be run length encoded
+        let rle = (8..=0x7fff).contains(&width);
+        let mut line = Vec::with_capacity(4 * width + 4);
+        for y in 0..image.height() {
+            let pixels: Vec<[u8; 4]> = (0..image.width()).map(|x| to_rgbe(&image.get_color(x, y))).collect();
+            line.clear();
+            if rle {
+                line.extend_from_slice(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+                for component in 0..4 {
+                    let data: Vec<u8> = pixels.iter().map(|pixel| pixel[component]).collect();
+                    write_rle(&data, &mut line);
+                }
+            } else {
+                line.extend(pixels.iter().flatten());
+            }
+            writer.write_all(&line)?;
+        }
+        Ok(())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_rgbe(rgbe: &[u8]) -> Color {
+        if rgbe[3] == 0 {
+            return Color::default();
+        }
+        let scale = 2f64.powi(rgbe[3] as i32 - 128 - 8);
+        Color::new(rgbe[0] as f64 * scale, rgbe[1] as f64 * scale, rgbe[2] as f64 * scale)
+    }
+
+    fn read_rle(data: &[u8], count: usize, position: &mut usize) -> Vec<u8> {
+        let mut values = Vec::new();
+        while values.len() < count {
+            let length = data[*position] as usize;
+            if length > 128 {
+                values.extend(std::iter::repeat_n(data[*position + 1], length - 128));
+                *position += 2;
+            } else {
+                values.extend_from_slice(&data[*position + 1..*position + 1 + length]);
+                *position += 1 + length;
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn test_rgbe() {
+        for color in &[Color::new(1.0, 0.5, 0.25), Color::new(1000.0, 3.0, 0.0), Color::new(0.01, 0.02, 0.003)] {
+            let back = from_rgbe(&to_rgbe(color));
+            let max = color.max_component();
+            assert!((back.r - color.r).abs() <= max / 128.0);
+            assert!((back.g - color.g).abs() <= max / 128.0);
+            assert!((back.b - color.b).abs() <= max / 128.0);
+        }
+        assert_eq!(to_rgbe(&Color::new(1.0, 0.0, 0.0)), [128, 0, 0, 129]);
+        assert_eq!(to_rgbe(&Color::new(-1.0, 0.0, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_rle() {
+        let data: Vec<u8> = (0..300).map(|i| if (100..250).contains(&i) { 7 } else { (i % 5) as u8 }).collect();
+        let mut encoded = Vec::new();
+        write_rle(&data, &mut encoded);
+        assert!(encoded.len() < data.len());
+        assert_eq!(read_rle(&encoded, data.len(), &mut 0), data);
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut image = Image::new(10, 2, Color::new(4.0, 2.0, 1.0));
+        image.set_color(3, 1, Color::new(0.0, 0.5, 100.0));
+        let mut out: Vec<u8> = Vec::new();
+        HdrEncoder::new().encode(&image, &mut out).unwrap();
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 10\n";
+        assert!(out.starts_with(header));
+        let mut position = header.len();
+        for y in 0..2 {
+            assert_eq!(&out[position..position + 4], &[2, 2, 0, 10]);
+            position += 4;
+            let components: Vec<Vec<u8>> = (0..4).map(|_| read_rle(&out, 10, &mut position)).collect();
+            for x in 0..10 {
+                let rgbe: Vec<u8> = components.iter().map(|component| component[x]).collect();
+                assert_eq!(from_rgbe(&rgbe), image.get_color(x as u16, y as u16));
+            }
+        }
+        assert_eq!(position, out.len());
+
+        // Too narrow for the run length encoding
+        let mut out: Vec<u8> = Vec::new();
+        HdrEncoder::new().encode(&Image::new(2, 1, Color::new(1.0, 0.0, 0.0)), &mut out).unwrap();
+        assert!(out.ends_with(&[128, 0, 0, 129, 128, 0, 0, 129]));
+    }
+}
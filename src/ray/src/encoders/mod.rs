@@ -0,0 +1,14 @@
+mod encoder;
+pub use encoder::*;
+
+mod png_encoder;
+pub use png_encoder::*;
+
+mod ppm_encoder;
+pub use ppm_encoder::*;
+
+mod hdr_encoder;
+pub use hdr_encoder::*;
+
+mod exr_encoder;
+pub use exr_encoder::*;
@@ -0,0 +1,26 @@
+use std::io::Write;
+
+use crate::{error::ImageError, image::Image};
+
+use super::ImageEncoder;
+
+#[derive(Default)]
+pub struct PngEncoder;
+
+impl PngEncoder {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ImageEncoder for PngEncoder {
+    fn encode(&self, image: &Image, writer: &mut dyn Write) -> Result<(), ImageError> {
+        let mut encoder = png::Encoder::new(writer, image.width() as u32, image.height() as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut png_writer = encoder.write_header()?;
+        png_writer.write_image_data(&image.to_rgb8())?;
+        png_writer.finish()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::Write;
+
+use crate::{error::ImageError, image::Image};
+
+use super::ImageEncoder;
+
+// http://netpbm.sourceforge.net/doc/ppm.html
+pub struct PpmEncoder {
+    ascii: bool,
+}
+
+impl PpmEncoder {
+    pub fn new(ascii: bool) -> Self {
+        Self { ascii }
+    }
+}
+
+impl ImageEncoder for PpmEncoder {
+    fn encode(&self, image: &Image, writer: &mut dyn Write) -> Result<(), ImageError> {
+        let magic = if self.ascii { "P3" } else { "P6" };
+        write!(writer, "{}\n{} {}\n255\n", magic, image.width(), image.height())?;
+
+        let data = image.to_rgb8();
+        if self.ascii {
+            let line_len = 3 * image.width() as usize;
+            for line in data.chunks(line_len.max(1)) {
+                let values: Vec<String> = line.iter().map(|v| v.to_string()).collect();
+                writeln!(writer, "{}", values.join(" "))?;
+            }
+        } else {
+            writer.write_all(&data)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn test_binary() {
+        let image = Image::new(2, 1, Color::new(1.0, 0.0, 0.5));
+        let mut out: Vec<u8> = Vec::new();
+        PpmEncoder::new(false).encode(&image, &mut out).unwrap();
+        assert_eq!(out, b"P6\n2 1\n255\n\xff\x00\x7f\xff\x00\x7f".to_vec());
+    }
+
+    #[test]
+    fn test_ascii() {
+        let image = Image::new(1, 2, Color::new(2.0, 0.0, 1.0));
+        let mut out: Vec<u8> = Vec::new();
+        PpmEncoder::new(true).encode(&image, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "P3\n1 2\n255\n255 0 255\n255 0 255\n");
+    }
+}
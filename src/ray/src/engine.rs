@@ -1,27 +1,69 @@
-use std::cmp::Ordering;
+use std::{
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+    thread,
+};
 
 use crate::{
     color::{Color, BLACK, WHITE},
     image::Image,
-    intersection::{Intersection, EPSILON},
-    light::Light,
-    ray::Ray,
-    things::Thing,
-    vector::Vector3d,
+    integrators::Integrator,
+    scene::Scene,
     world::World,
 };
 
+pub const TILE_SIZE: u16 = 32;
+
+// A rectangular block of pixels rendered by a single thread.
+struct Tile {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
 pub struct Engine {
-    world: World,
+    scene: Scene,
+    integrator: Box<dyn Integrator>,
+    threads: usize,
 }
 
 impl Engine {
     pub fn new(world: World) -> Self {
-        Self { world }
+        Self::with_threads(world, 1)
+    }
+
+    // Renders with the integrator selected by the world.
+    pub fn with_threads(world: World, threads: usize) -> Self {
+        let integrator = world.integrator().integrator();
+        Self::with_integrator(world, threads, integrator)
+    }
+
+    pub fn with_integrator(world: World, threads: usize, integrator: Box<dyn Integrator>) -> Self {
+        Self {
+            scene: Scene::new(world),
+            integrator,
+            threads: threads.max(1),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
     }
 
     pub fn generate(&self) -> Image {
-        let (width, height) = self.world.camera().get_pixel_size();
+        if self.threads == 1 {
+            self.generate_serial()
+        } else {
+            self.generate_parallel()
+        }
+    }
+
+    fn generate_serial(&self) -> Image {
+        let (width, height) = self.scene.world().camera().get_pixel_size();
         let mut image = Image::new(width, height, WHITE);
 
         for y in 0..height {
@@ -33,153 +75,140 @@ impl Engine {
         image
     }
 
-    fn get_pixel_at(&self, x: u16, y: u16) -> Color {
-        let ray = self.world.camera().get_ray(x, y);
-        self.launch_ray(&ray, self.world.max_recurions())
-    }
+    // Threads pick the next tile to render from a shared counter, every pixel is
+    // computed exactly as in the serial path so the output does not depend on the
+    // number of threads.
+    fn generate_parallel(&self) -> Image {
+        let (width, height) = self.scene.world().camera().get_pixel_size();
+        let tiles = Self::split_in_tiles(width, height);
+        let next_tile = AtomicUsize::new(0);
+
+        let rendered: Vec<(usize, Vec<Color>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads.min(tiles.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut rendered = Vec::new();
+                        loop {
+                            let index = next_tile.fetch_add(1, AtomicOrdering::Relaxed);
+                            match tiles.get(index) {
+                                Some(tile) => rendered.push((index, self.render_tile(tile))),
+                                None => break rendered,
+                            }
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("Render thread panicked"))
+                .collect()
+        });
 
-    fn launch_ray(&self, ray: &Ray, max_recurions: u16) -> Color {
-        match self.find_intersection(&ray) {
-            Some(inter) => {
-                let thing = self.world.thing(inter.thing_index());
-
-                self.ambiant_component(&inter, thing)
-                    + self.diffuse_component(&inter, thing)
-                    + self.specular_component(&inter, thing, ray, max_recurions)
-                    + self.refraction_component(&inter, thing, &ray, max_recurions)
+        let mut image = Image::new(width, height, WHITE);
+        for (index, colors) in rendered {
+            let tile = &tiles[index];
+            let mut colors = colors.into_iter();
+            for y in tile.y..tile.y + tile.height {
+                for x in tile.x..tile.x + tile.width {
+                    image.set_color(x, y, colors.next().unwrap());
+                }
             }
-            None => BLACK,
         }
+        image
     }
 
-    fn ambiant_component(&self, intersection: &Intersection, thing: &Box<dyn Thing>) -> Color {
-        &thing.ambiant(intersection.position()) * self.world.ambiant_light()
+    fn split_in_tiles(width: u16, height: u16) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y in (0..height).step_by(TILE_SIZE as usize) {
+            for x in (0..width).step_by(TILE_SIZE as usize) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: TILE_SIZE.min(width - x),
+                    height: TILE_SIZE.min(height - y),
+                });
+            }
+        }
+        tiles
     }
 
-    fn diffuse_component(&self, intersection: &Intersection, thing: &Box<dyn Thing>) -> Color {
-        self.world
-            .lights()
-            .iter()
-            .filter_map(|light| self.diffuse_component_from_one_light(intersection, thing, light))
-            .sum()
+    fn render_tile(&self, tile: &Tile) -> Vec<Color> {
+        let mut colors = Vec::with_capacity(tile.width as usize * tile.height as usize);
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                colors.push(self.get_pixel_at(x, y));
+            }
+        }
+        colors
     }
 
-    fn diffuse_component_from_one_light(
-        &self,
-        intersection: &Intersection,
-        thing: &Box<dyn Thing>,
-        light: &Light,
-    ) -> Option<Color> {
-        let intersection_to_light = light.position() - intersection.position();
-        let distance_to_light = intersection_to_light.mag();
-        let ray_to_light = Ray::new(intersection.position(), &intersection_to_light);
-        let thing_normal = intersection.normal();
-        let diffusion_coef = match (ray_to_light.dir().norm().unwrap()).dot(&thing_normal) {
-            d if d > f64::EPSILON => d,
-            _ => 0.0,
-        };
-
-        if diffusion_coef > 0.0 {
-            match self.find_intersection(&ray_to_light) {
-                Some(obstruction) if obstruction.distance() < distance_to_light => None,
-                _ => Some(
-                    (light.color() * &thing.diffuse(intersection.position())).scale(diffusion_coef),
-                ),
+    fn get_pixel_at(&self, x: u16, y: u16) -> Color {
+        let world = self.scene.world();
+        world.sampling().render_pixel(x, y, |image_x, image_y, random| {
+            match world.camera().get_ray(image_x, image_y, random) {
+                Some(ray) => self.integrator.radiance(&ray, &self.scene, random),
+                None => BLACK,
             }
-        } else {
-            None
-        }
+        })
     }
+}
 
-    fn specular_component(
-        &self,
-        intersection: &Intersection,
-        thing: &Box<dyn Thing>,
-        ray: &Ray,
-        max_recurions: u16,
-    ) -> Color {
-        if max_recurions == 0 || thing.specular(intersection.position()).is_black() {
-            BLACK
-        } else {
-            let new_ray_dir = Self::find_specular_direction(ray.dir(), intersection.normal());
-            let new_ray = Ray::new(intersection.position(), &new_ray_dir);
-            self.launch_ray(&new_ray, max_recurions - 1) * thing.specular(intersection.position())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        integrators::NormalIntegrator,
+        sampling::{SamplePattern, Sampler},
+        test_worlds::test_world,
+    };
+
+    #[test]
+    fn test_parallel_is_identical_to_serial() {
+        let serial = Engine::new(test_world()).generate();
+        let parallel = Engine::with_threads(test_world(), 3).generate();
+
+        assert_eq!(serial.width(), parallel.width());
+        assert_eq!(serial.height(), parallel.height());
+        for y in 0..serial.height() {
+            for x in 0..serial.width() {
+                assert_eq!(serial.get_color(x, y), parallel.get_color(x, y));
+            }
         }
     }
 
-    fn find_specular_direction(ray_dir: &Vector3d, normal: &Vector3d) -> Vector3d {
-        // https://www.scratchapixel.com/lessons/3d-basic-rendering/introduction-to-shading/reflection-refraction-fresnel
-        ray_dir - &(normal * &(2.0 * ray_dir.dot(normal)).into())
+    #[test]
+    fn test_supersampling_does_not_depend_on_threads() {
+        let sampling = Sampler::new(4, SamplePattern::Jittered, "gaussian".parse().unwrap());
+        let mut world = test_world();
+        world.set_sampling(sampling.clone());
+        let serial = Engine::new(world).generate();
+        let mut world = test_world();
+        world.set_sampling(sampling);
+        let parallel = Engine::with_threads(world, 3).generate();
+
+        for y in 0..serial.height() {
+            for x in 0..serial.width() {
+                assert_eq!(serial.get_color(x, y), parallel.get_color(x, y));
+            }
+        }
     }
 
-    fn refraction_component(
-        &self,
-        intersection: &Intersection,
-        thing: &Box<dyn Thing>,
-        ray: &Ray,
-        max_recurions: u16,
-    ) -> Color {
-        if max_recurions == 0 || thing.refraction(intersection.position()).is_black() {
-            BLACK
-        } else {
-            let material_refraction_index = thing.refraction_ratio();
-            let ratio = if intersection.collide_from_outside() {
-                material_refraction_index
-            } else {
-                1.0 / material_refraction_index
-            };
-
-            let c = -intersection.normal().dot(ray.dir());
-
-            let refraction_vector = ray.dir() * &ratio.into()
-                + intersection.normal()
-                    * &(ratio * c - (1.0 - (ratio * ratio) * (1.0 - (c * c))).sqrt()).into();
-
-            let new_ray = Ray::new(intersection.position(), &refraction_vector);
-
-            self.launch_ray(&new_ray, max_recurions - 1) * thing.refraction(intersection.position())
-        }
+    #[test]
+    fn test_custom_integrator() {
+        let engine = Engine::with_integrator(test_world(), 2, Box::new(NormalIntegrator::new()));
+        let image = engine.generate();
+        let (width, height) = (image.width(), image.height());
+        // The sphere faces the camera in the middle of the image
+        let center = image.get_color(width / 2, height / 2);
+        assert!(center.r < 0.01 && (center.g - 0.5).abs() < 0.01 && (center.b - 0.5).abs() < 0.01);
+        assert_eq!(image.get_color(0, 0), Color::new(0.0, 0.0, 0.0));
     }
 
-    fn find_intersection(&self, ray: &Ray) -> Option<Intersection> {
-        self.world
-            .things()
-            .iter()
-            .enumerate()
-            // Find intersections with thing
-            .flat_map(|(index, thing)| {
-                let intersections = thing.intersect(ray);
-                let intersections_with_index: Vec<(usize, Vector3d)> = intersections
-                    .iter()
-                    .map(|intersection| (index.clone(), intersection.clone()))
-                    .collect();
-                intersections_with_index.into_iter()
-            })
-            // Create intersection object
-            .map(|(thing_index, position)| {
-                let normal = self.world.thing(thing_index).normal(&position);
-                let (normal, collide_from_outside) = if normal.dot(ray.dir()) > 0.0 {
-                    (-normal, false)
-                } else {
-                    (normal, true)
-                };
-                Intersection::new(
-                    thing_index,
-                    position.clone(),
-                    normal,
-                    (ray.start() - &position).mag(),
-                    collide_from_outside,
-                )
-            })
-            // Avoid self intersection
-            .filter(|intersection| intersection.distance() > EPSILON)
-            .filter(|intersection| (intersection.position() - ray.start()).dot(ray.dir()) > 0.0)
-            .min_by(|a, b| {
-                if a.distance() < b.distance() {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                }
-            })
+    #[test]
+    fn test_tiles_cover_image() {
+        let tiles = Engine::split_in_tiles(70, 33);
+        assert_eq!(tiles.len(), 6);
+        let covered: usize = tiles.iter().map(|t| t.width as usize * t.height as usize).sum();
+        assert_eq!(covered, 70 * 33);
     }
 }
@@ -0,0 +1,78 @@
+use math::error::MatrixError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ImageError {
+    #[error("Unsupported image format: {0}")]
+    UnsupportedFormat(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("PNG encoding error: {0}")]
+    Png(#[from] png::EncodingError),
+    #[error("OpenEXR encoding error: {0}")]
+    Exr(#[from] exr::error::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum MeshError {
+    #[error("The {0} index {1} is out of bounds (buffer size {2})")]
+    IndexOutOfBounds(&'static str, usize, usize),
+    #[error("{1} {0} indices given for {2} triangles")]
+    IndexCountMismatch(&'static str, usize, usize),
+}
+
+#[derive(Error, Debug)]
+pub enum ObjError {
+    #[error("{0}:{1}: {2}")]
+    Parse(String, usize, String),
+    #[error("Unable to read {0}: {1}")]
+    Io(String, std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum TextureError {
+    #[error("Unable to read {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Unable to decode {0}: {1}")]
+    Decode(String, String),
+    #[error("Unsupported texture format: {0}")]
+    UnsupportedFormat(String),
+}
+
+#[derive(Error, Debug)]
+pub enum GeometryError {
+    #[error("The {0} cannot be the zero vector")]
+    ZeroVector(&'static str),
+}
+
+#[derive(Error, Debug)]
+pub enum ResourceError {
+    #[error(transparent)]
+    Obj(#[from] ObjError),
+    #[error(transparent)]
+    Texture(#[from] TextureError),
+    #[error(transparent)]
+    Mesh(#[from] MeshError),
+    #[error("Unknown geometry '{0}'")]
+    UnknownGeometry(String),
+    #[error("Geometry '{0}' is already shared by its instances, the resources can only be loaded once")]
+    GeometryShared(String),
+}
+
+#[derive(Error, Debug)]
+pub enum CameraError {
+    #[error(transparent)]
+    LookAt(#[from] MatrixError),
+    #[error("The field of view must be between 0 and 180 degrees, got {0}")]
+    InvalidFov(f64),
+    #[error("The image must be at least one pixel wide and high")]
+    EmptyImage,
+    #[error("Invalid lens: {0}")]
+    InvalidLens(&'static str),
+    #[error("The right, up and direction vectors must be orthonormal")]
+    InvalidBasis,
+    #[error("The focal distance must be strictly positive, got {0}")]
+    InvalidFocalDistance(f64),
+    #[error("The image plane must have a strictly positive size and pixel density")]
+    InvalidImagePlane,
+}
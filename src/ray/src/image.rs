@@ -24,10 +24,10 @@ impl Image {
         }
     }
     pub fn get_color(&self, x: u16, y: u16) -> Color {
-        return self.data[y as usize][x as usize].clone();
+        self.data[y as usize][x as usize].clone()
     }
     pub fn set_color(&mut self, x: u16, y: u16, color: Color) {
-        return self.data[y as usize][x as usize] = color;
+        self.data[y as usize][x as usize] = color;
     }
     
     pub fn width(&self) -> u16 {
@@ -37,4 +37,16 @@ impl Image {
     pub fn height(&self) -> u16 {
         self.height
     }
+
+    // 8 bits per channel, row 0 is the top of the image.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.data
+            .iter()
+            .flat_map(|line| line.iter())
+            .flat_map(|color| {
+                let color = color.to_drawing_color();
+                vec![color.r as u8, color.g as u8, color.b as u8]
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,178 @@
+// Integrators showing one property of the visible things, to inspect a scene.
+use crate::{
+    color::{Color, BLACK},
+    ray::Ray,
+    sampling::{cosine_hemisphere, Random},
+    scene::Scene,
+    vector::Vector3d,
+};
+
+use super::{shading_point, Integrator};
+
+// Outward normal, each component mapped from [-1, 1] to [0, 1].
+#[derive(Default)]
+pub struct NormalIntegrator;
+
+impl NormalIntegrator {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Integrator for NormalIntegrator {
+    fn radiance(&self, ray: &Ray, scene: &Scene, _random: &mut Random) -> Color {
+        match scene.find_intersection(ray) {
+            Some(intersection) => {
+                let thing = scene.world().thing(intersection.thing_index());
+                let normal = thing.normal(intersection.hit());
+                Color::new((normal.x + 1.0) / 2.0, (normal.y + 1.0) / 2.0, (normal.z + 1.0) / 2.0)
+            }
+            None => BLACK,
+        }
+    }
+}
+
+// Distance to the camera, white up close and black from `max_distance`.
+pub struct DepthIntegrator {
+    max_distance: f64,
+}
+
+impl DepthIntegrator {
+    pub fn new(max_distance: f64) -> Self {
+        Self { max_distance }
+    }
+}
+
+impl Integrator for DepthIntegrator {
+    fn radiance(&self, ray: &Ray, scene: &Scene, _random: &mut Random) -> Color {
+        match scene.find_intersection(ray) {
+            Some(intersection) => {
+                let shade = 1.0 - (intersection.distance() / self.max_distance).min(1.0);
+                Color::new(shade, shade, shade)
+            }
+            None => BLACK,
+        }
+    }
+}
+
+// Texture coordinates in the red and green channels.
+#[derive(Default)]
+pub struct UvIntegrator;
+
+impl UvIntegrator {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Integrator for UvIntegrator {
+    fn radiance(&self, ray: &Ray, scene: &Scene, _random: &mut Random) -> Color {
+        match scene.find_intersection(ray) {
+            Some(intersection) => {
+                let thing = scene.world().thing(intersection.thing_index());
+                let uv = thing.get_uv_mapping(intersection.hit());
+                Color::new(uv.x.rem_euclid(1.0), uv.y.rem_euclid(1.0), 0.0)
+            }
+            None => BLACK,
+        }
+    }
+}
+
+// Color of the materials, without any lighting.
+#[derive(Default)]
+pub struct AlbedoIntegrator;
+
+impl AlbedoIntegrator {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Integrator for AlbedoIntegrator {
+    fn radiance(&self, ray: &Ray, scene: &Scene, _random: &mut Random) -> Color {
+        match scene.find_intersection(ray) {
+            Some(intersection) => {
+                let thing = scene.world().thing(intersection.thing_index());
+                let point = shading_point(&intersection, thing);
+                thing.surface_at(intersection.hit()).albedo(&point)
+            }
+            None => BLACK,
+        }
+    }
+}
+
+// Part of the hemisphere above the hit point that is not hidden by a thing
+// closer than `max_distance`, weighted by the cosine to the normal.
+pub struct AmbientOcclusionIntegrator {
+    samples: usize,
+    max_distance: f64,
+}
+
+impl AmbientOcclusionIntegrator {
+    pub fn new(samples: usize, max_distance: f64) -> Self {
+        Self {
+            samples: samples.max(1),
+            max_distance,
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn radiance(&self, ray: &Ray, scene: &Scene, random: &mut Random) -> Color {
+        match scene.find_intersection(ray) {
+            Some(intersection) => {
+                let unoccluded = (0..self.samples)
+                    .filter(|_| {
+                        let dir: Vector3d = cosine_hemisphere(intersection.normal(), random);
+                        scene.is_unobstructed(&Ray::new(intersection.position(), &dir), self.max_distance)
+                    })
+                    .count();
+                let shade = unoccluded as f64 / self.samples as f64;
+                Color::new(shade, shade, shade)
+            }
+            None => BLACK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_worlds::diffuse_world;
+
+    fn render(integrator: &dyn Integrator, scene: &Scene, ray: &Ray) -> Color {
+        integrator.radiance(ray, scene, &mut Random::new(1))
+    }
+
+    #[test]
+    fn test_debug_integrators() {
+        let scene = Scene::new(diffuse_world());
+        // Hits the sphere centered on (5, 0, 0) at (4, 0, 0)
+        let to_sphere = Ray::new(&Vector3d::zero(), &Vector3d::x_axis());
+        let to_sky = Ray::new(&Vector3d::zero(), &Vector3d::z_axis());
+
+        assert_eq!(render(&NormalIntegrator::new(), &scene, &to_sphere), Color::new(0.0, 0.5, 0.5));
+        assert_eq!(render(&DepthIntegrator::new(8.0), &scene, &to_sphere), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(render(&AlbedoIntegrator::new(), &scene, &to_sphere), Color::new(0.8, 0.2, 0.2));
+        for integrator in &[
+            &NormalIntegrator::new() as &dyn Integrator,
+            &DepthIntegrator::new(8.0),
+            &UvIntegrator::new(),
+            &AlbedoIntegrator::new(),
+            &AmbientOcclusionIntegrator::new(4, 1.0),
+        ] {
+            assert_eq!(render(*integrator, &scene, &to_sky), BLACK);
+        }
+    }
+
+    #[test]
+    fn test_ambient_occlusion() {
+        let scene = Scene::new(diffuse_world());
+        let integrator = AmbientOcclusionIntegrator::new(64, 100.0);
+        // The floor right under the sphere is mostly hidden, far from it nothing is
+        let under_sphere = Ray::new(&Vector3d::new(5.0, 1.05, 0.0), &-Vector3d::z_axis());
+        let far_away = Ray::new(&Vector3d::new(50.0, 50.0, 0.0), &-Vector3d::z_axis());
+        assert!(render(&integrator, &scene, &under_sphere).r < 0.9);
+        assert_eq!(render(&integrator, &scene, &far_away), Color::new(1.0, 1.0, 1.0));
+    }
+}
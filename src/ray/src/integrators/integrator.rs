@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use crate::{color::Color, ray::Ray, sampling::Random, scene::Scene};
+
+use super::{
+    AlbedoIntegrator, AmbientOcclusionIntegrator, DepthIntegrator, NormalIntegrator, PathIntegrator,
+    UvIntegrator, WhittedIntegrator,
+};
+
+// Computes the light coming back along a camera ray. `random` is the random stream
+// of the pixel, so that renders do not depend on the number of threads.
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, ray: &Ray, scene: &Scene, random: &mut Random) -> Color;
+}
+
+// Integrators selectable from the scene file and the command line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegratorKind {
+    // Direct light from the point lights, mirror reflection and refraction
+    #[default]
+    Whitted,
+    // Monte Carlo path tracing with indirect diffuse light
+    Path,
+    Normals,
+    Depth,
+    Uv,
+    Albedo,
+    AmbientOcclusion,
+}
+
+impl IntegratorKind {
+    pub const NAMES: [&'static str; 7] = ["whitted", "path", "normals", "depth", "uv", "albedo", "ambient_occlusion"];
+
+    pub fn integrator(&self) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::Whitted => Box::new(WhittedIntegrator::new()),
+            IntegratorKind::Path => Box::new(PathIntegrator::new()),
+            IntegratorKind::Normals => Box::new(NormalIntegrator::new()),
+            IntegratorKind::Depth => Box::new(DepthIntegrator::new(20.0)),
+            IntegratorKind::Uv => Box::new(UvIntegrator::new()),
+            IntegratorKind::Albedo => Box::new(AlbedoIntegrator::new()),
+            IntegratorKind::AmbientOcclusion => Box::new(AmbientOcclusionIntegrator::new(16, 1.0)),
+        }
+    }
+}
+
+impl FromStr for IntegratorKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "whitted" => Ok(IntegratorKind::Whitted),
+            "path" => Ok(IntegratorKind::Path),
+            "normals" => Ok(IntegratorKind::Normals),
+            "depth" => Ok(IntegratorKind::Depth),
+            "uv" => Ok(IntegratorKind::Uv),
+            "albedo" => Ok(IntegratorKind::Albedo),
+            "ambient_occlusion" => Ok(IntegratorKind::AmbientOcclusion),
+            _ => Err(format!("Unknown integrator: {}", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        for name in IntegratorKind::NAMES.iter() {
+            let kind: IntegratorKind = name.parse().unwrap();
+            assert_eq!(serde_json::to_string(&kind).unwrap(), format!("\"{}\"", name));
+        }
+        assert!("photon_mapping".parse::<IntegratorKind>().is_err());
+    }
+}
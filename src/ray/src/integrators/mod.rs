@@ -0,0 +1,14 @@
+mod integrator;
+pub use integrator::*;
+
+mod shading;
+pub use shading::*;
+
+mod whitted;
+pub use whitted::*;
+
+mod path;
+pub use path::*;
+
+mod debug;
+pub use debug::*;
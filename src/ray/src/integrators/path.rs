@@ -0,0 +1,153 @@
+use crate::{
+    color::{Color, BLACK, WHITE},
+    ray::Ray,
+    sampling::Random,
+    scene::Scene,
+};
+
+use super::{ambiant_light, direct_light, emitted_light, shading_point, Integrator};
+
+// Path depth from which paths are randomly terminated.
+const RUSSIAN_ROULETTE_DEPTH: u16 = 3;
+
+// Monte Carlo path tracing, one path per sample: the lights are sampled at every
+// vertex (next event estimation) then the path follows a direction sampled by the material.
+#[derive(Default)]
+pub struct PathIntegrator;
+
+impl PathIntegrator {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn radiance(&self, camera_ray: &Ray, scene: &Scene, random: &mut Random) -> Color {
+        let max_depth = scene.world().max_recurions();
+        let mut radiance = BLACK;
+        let mut throughput = WHITE;
+        let mut ray = Ray::new(camera_ray.start(), camera_ray.dir());
+        // Area lights met after a diffuse bounce are already counted by the light sampling
+        let mut sees_emission = true;
+
+        for depth in 0..=max_depth {
+            let intersection = scene.find_intersection(&ray);
+            if let Some(emitted) = emitted_light(scene, &ray, intersection.as_ref()) {
+                if sees_emission {
+                    radiance = radiance + &throughput * &emitted;
+                }
+                break;
+            }
+            let intersection = match intersection {
+                Some(intersection) => intersection,
+                None => break,
+            };
+            let thing = scene.world().thing(intersection.thing_index());
+            let position = intersection.position();
+
+            let material = thing.surface_at(intersection.hit());
+            let point = shading_point(&intersection, thing);
+
+            // Emissive materials are not sampled as lights
+            let mut direct = material.emission(&point) + direct_light(scene, &intersection, thing, &ray, random);
+            // The ambiant light stands for the indirect light of the Whitted integrator, the paths
+            // only add it where they start not to count it once more at every bounce
+            if depth == 0 {
+                direct = direct + ambiant_light(scene, &intersection, thing);
+            }
+            radiance = radiance + &throughput * &direct;
+            if depth == max_depth {
+                break;
+            }
+
+            let to_viewer = -ray.dir().clone();
+            let sample = match material.sample(&point, &to_viewer, random) {
+                Some(sample) => sample,
+                None => break,
+            };
+            sees_emission = sample.specular;
+            throughput = &throughput * &sample.weight;
+
+            if depth >= RUSSIAN_ROULETTE_DEPTH {
+                let survival = throughput.max_component().min(0.95);
+                if random.next_f64() >= survival {
+                    break;
+                }
+                throughput = throughput.scale(1.0 / survival);
+            }
+            ray = Ray::new(position, &sample.direction);
+        }
+        radiance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cameras::PerspectiveCamera,
+        engine::Engine,
+        integrators::IntegratorKind,
+        sampling::{Filter, SamplePattern, Sampler},
+        surfaces::{ConstColor, Emissive},
+        test_worlds::{const_surface, diffuse_surface, diffuse_world, is_finite},
+        things::{Sphere, Thing},
+        vector::Vector3d,
+        world::World,
+    };
+
+    #[test]
+    fn test_path_tracing_adds_indirect_light() {
+        let whitted = Engine::new(diffuse_world()).generate();
+        let mut world = diffuse_world();
+        world.set_integrator(IntegratorKind::Path);
+        world.set_sampling(Sampler::new(2, SamplePattern::Jittered, Filter::default()));
+        let path = Engine::with_threads(world, 2).generate();
+
+        let mut brighter = 0;
+        for y in 0..path.height() {
+            for x in 0..path.width() {
+                let (direct, total) = (whitted.get_color(x, y), path.get_color(x, y));
+                assert!(is_finite(&total));
+                if total.average() > direct.average() + 1e-3 {
+                    brighter += 1;
+                }
+            }
+        }
+        // Shadows and the bottom of the sphere receive light bounced by the floor
+        assert!(brighter > (path.width() as usize * path.height() as usize) / 10);
+    }
+
+    #[test]
+    fn test_white_furnace() {
+        // Grey ball inside a sphere glowing uniformly, the ball sends back its albedo
+        let glow = Emissive::new(Box::new(ConstColor::new(WHITE)));
+        let sky = Sphere::new(Vector3d::zero(), 20.0, Box::new(glow));
+        let ball = Sphere::new(Vector3d::new(5.0, 0.0, 0.0), 1.0, diffuse_surface(Color::new(0.5, 0.5, 0.5)));
+        let things: Vec<Box<dyn Thing>> = vec![Box::new(sky), Box::new(ball)];
+        let scene = Scene::new(World::new(Box::new(PerspectiveCamera::default()), things, vec![], BLACK, 5));
+        let mut random = Random::new(7);
+        let average = |dir: Vector3d, random: &mut Random| {
+            let ray = Ray::new(&Vector3d::new(0.0, 0.0, 0.3), &dir);
+            let total: Color = (0..200).map(|_| PathIntegrator::new().radiance(&ray, &scene, random)).sum();
+            total.scale(1.0 / 200.0)
+        };
+        let ball = average(Vector3d::x_axis(), &mut random);
+        assert!((ball.r - 0.5).abs() < 1e-9, "{:?}", ball);
+        assert_eq!(average(-Vector3d::x_axis(), &mut random), WHITE);
+    }
+
+    #[test]
+    fn test_ambiant_light_added_once() {
+        // Inside a sphere sending back half of the light, lit by the ambiant light only
+        let surface = const_surface(Color::new(0.2, 0.2, 0.2), Color::new(0.5, 0.5, 0.5), BLACK, BLACK, 1.0);
+        let room = Sphere::new(Vector3d::zero(), 10.0, Box::new(surface));
+        let world = World::new(Box::new(PerspectiveCamera::default()), vec![Box::new(room)], vec![], WHITE, 5);
+        let scene = Scene::new(world);
+        let ray = Ray::new(&Vector3d::zero(), &Vector3d::x_axis());
+        let mut random = Random::new(3);
+        for _ in 0..20 {
+            assert_eq!(PathIntegrator::new().radiance(&ray, &scene, &mut random), Color::new(0.2, 0.2, 0.2));
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// Shading terms shared by the integrators.
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    intersection::Intersection,
+    lights::{AreaLight, LightSource},
+    ray::Ray,
+    sampling::Random,
+    scene::Scene,
+    surfaces::{ShadingPoint, TextureContext},
+    things::Thing,
+    vector::Vector3d,
+};
+
+// Intersection as seen by the material of the thing.
+pub fn shading_point(intersection: &Intersection, thing: &dyn Thing) -> ShadingPoint {
+    let position = intersection.position();
+    let normal = intersection.normal().clone();
+    let outward_normal = if intersection.collide_from_outside() {
+        normal.clone()
+    } else {
+        -normal.clone()
+    };
+    ShadingPoint {
+        texture: TextureContext {
+            uv: thing.get_uv_mapping(intersection.hit()),
+            position: position.clone(),
+            object_position: thing.object_position(intersection.hit()),
+            normal: outward_normal,
+        },
+        normal,
+        from_outside: intersection.collide_from_outside(),
+    }
+}
+
+pub fn ambiant_light(scene: &Scene, intersection: &Intersection, thing: &dyn Thing) -> Color {
+    let point = shading_point(intersection, thing);
+    &thing.surface_at(intersection.hit()).ambiant(&point) * scene.world().ambiant_light()
+}
+
+// Light received from the lights that are not hidden by another thing and reflected
+// toward the origin of the ray: diffuse light and specular highlights.
+pub fn direct_light(
+    scene: &Scene,
+    intersection: &Intersection,
+    thing: &dyn Thing,
+    ray: &Ray,
+    random: &mut Random,
+) -> Color {
+    let to_viewer = -ray.dir().clone();
+    let from_points: Color = scene
+        .world()
+        .lights()
+        .iter()
+        .filter_map(|light| light_from_one_light(scene, intersection, thing, &to_viewer, light.as_ref()))
+        .sum();
+    let from_areas: Color = scene
+        .world()
+        .area_lights()
+        .iter()
+        .map(|light| light_from_area_light(scene, intersection, thing, &to_viewer, light, random))
+        .sum();
+    from_points + from_areas
+}
+
+// Light of a point light coming from `to_light` with an incidence cosine `cos` reflected toward
+// the viewer: BSDF * cos * irradiance like the sampled directions, the irradiance being PI * `light`
+// so that a white lambertian surface lit head-on reflects the color of the light.
+fn reflected_light(
+    intersection: &Intersection,
+    thing: &dyn Thing,
+    to_viewer: &Vector3d,
+    to_light: &Vector3d,
+    light: &Color,
+    cos: f64,
+) -> Color {
+    let point = shading_point(intersection, thing);
+    let bsdf = thing.surface_at(intersection.hit()).evaluate(&point, to_viewer, to_light);
+    (light * &bsdf).scale(PI * cos)
+}
+
+fn light_from_one_light(
+    scene: &Scene,
+    intersection: &Intersection,
+    thing: &dyn Thing,
+    to_viewer: &Vector3d,
+    light: &dyn LightSource,
+) -> Option<Color> {
+    let illumination = light.illuminate(intersection.position())?;
+    let ray_to_light = Ray::new(intersection.position(), &illumination.direction);
+    let thing_normal = intersection.normal();
+    let diffusion_coef = match ray_to_light.dir().dot(thing_normal) {
+        d if d > f64::EPSILON => d,
+        _ => 0.0,
+    };
+
+    if diffusion_coef > 0.0 && scene.is_unobstructed(&ray_to_light, illumination.distance) {
+        Some(reflected_light(
+            intersection,
+            thing,
+            to_viewer,
+            ray_to_light.dir(),
+            &illumination.color,
+            diffusion_coef,
+        ))
+    } else {
+        None
+    }
+}
+
+// Average of the light received through `samples` shadow rays toward random points
+// of the light, the partly hidden lights giving soft shadows. The color of the light is
+// its radiance, as seen by the camera rays.
+fn light_from_area_light(
+    scene: &Scene,
+    intersection: &Intersection,
+    thing: &dyn Thing,
+    to_viewer: &Vector3d,
+    light: &AreaLight,
+    random: &mut Random,
+) -> Color {
+    let point = shading_point(intersection, thing);
+    let material = thing.surface_at(intersection.hit());
+    let samples = light.samples();
+    let received: Color = (0..samples)
+        .filter_map(|_| light.sample(intersection.position(), random))
+        .filter_map(|sample| {
+            let intersection_to_light = &sample.position - intersection.position();
+            let distance_to_light = intersection_to_light.mag();
+            let ray_to_light = Ray::new(intersection.position(), &intersection_to_light);
+            let diffusion_coef = ray_to_light.dir().dot(intersection.normal());
+            if diffusion_coef > f64::EPSILON && scene.is_unobstructed(&ray_to_light, distance_to_light) {
+                // BSDF * cos / pdf, the density of the sample being the inverse of its solid angle
+                let bsdf = material.evaluate(&point, to_viewer, ray_to_light.dir());
+                Some((light.color() * &bsdf).scale(diffusion_coef * sample.solid_angle))
+            } else {
+                None
+            }
+        })
+        .sum();
+    received.scale(1.0 / samples as f64)
+}
+
+// Light emitted toward the ray origin when the first thing met by the ray is an area light.
+pub fn emitted_light(scene: &Scene, ray: &Ray, intersection: Option<&Intersection>) -> Option<Color> {
+    let (light, distance) = scene.find_area_light(ray)?;
+    match intersection {
+        Some(intersection) if intersection.distance() <= distance => None,
+        _ => Some(light.color().clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cameras::PerspectiveCamera,
+        color::{BLACK, WHITE},
+        lights::{AreaShape, PointLight},
+        surfaces::Highlight,
+        test_worlds::{const_surface, diffuse_surface},
+        things::{Plane, Sphere},
+        world::World,
+    };
+
+    // Ball between a floor and a disk light
+    fn soft_shadow_scene(with_ball: bool) -> Scene {
+        let floor = Plane::new(Vector3d::new(0.0, 0.0, -1.0), Vector3d::z_axis(), diffuse_surface(WHITE)).unwrap();
+        let mut things: Vec<Box<dyn Thing>> = vec![Box::new(floor)];
+        if with_ball {
+            things.push(Box::new(Sphere::new(Vector3d::zero(), 0.5, diffuse_surface(WHITE))));
+        }
+        let mut world = World::new(Box::new(PerspectiveCamera::default()), things, vec![], BLACK, 1);
+        let disk = AreaShape::Disk {
+            center: Vector3d::new(0.0, 0.0, 3.0),
+            normal: -Vector3d::z_axis(),
+            radius: 1.0,
+        };
+        world.add_area_light(AreaLight::new(disk, WHITE, 256));
+        Scene::new(world)
+    }
+
+    fn floor_light(scene: &Scene, x: f64) -> f64 {
+        let ray = Ray::new(&Vector3d::new(x, 0.0, -0.9), &-Vector3d::z_axis());
+        let intersection = scene.find_intersection(&ray).unwrap();
+        let thing = scene.world().thing(intersection.thing_index());
+        direct_light(scene, &intersection, thing, &ray, &mut Random::new(5)).r
+    }
+
+    #[test]
+    fn test_area_light_soft_shadow() {
+        let (shadowed, unshadowed) = (soft_shadow_scene(true), soft_shadow_scene(false));
+        let visible_part = |x: f64| floor_light(&shadowed, x) / floor_light(&unshadowed, x);
+        assert_eq!(visible_part(0.0), 0.0);
+        assert!((visible_part(3.0) - 1.0).abs() < 1e-9);
+        // The shadow gets lighter progressively away from the ball
+        let penumbra = (0..60)
+            .map(|step| visible_part(step as f64 * 0.05))
+            .filter(|part| *part > 0.1 && *part < 0.9)
+            .count();
+        assert!(penumbra >= 3);
+    }
+
+    // White floor lit by a light of radiance 1 only
+    fn light_on_floor(shape: AreaShape) -> f64 {
+        let floor = Plane::new(Vector3d::new(0.0, 0.0, -1.0), Vector3d::z_axis(), diffuse_surface(WHITE)).unwrap();
+        let mut world = World::new(Box::new(PerspectiveCamera::default()), vec![Box::new(floor)], vec![], BLACK, 1);
+        world.add_area_light(AreaLight::new(shape, WHITE, 4096));
+        floor_light(&Scene::new(world), 0.0)
+    }
+
+    #[test]
+    fn test_area_light_falloff() {
+        let disk = |height: f64, radius: f64| AreaShape::Disk {
+            center: Vector3d::new(0.0, 0.0, height - 1.0),
+            normal: -Vector3d::z_axis(),
+            radius,
+        };
+        // The floor sends back the square of the sine of the half angle under which the light is seen
+        assert!((light_on_floor(disk(1.0, 1.0)) - 0.5).abs() < 0.01);
+        let sphere = AreaShape::Sphere {
+            center: Vector3d::new(0.0, 0.0, 1.0),
+            radius: 1.0,
+        };
+        assert!((light_on_floor(sphere) - 0.25).abs() < 0.01);
+
+        // A small light twice as far gives a quarter of the light
+        let ratio = light_on_floor(disk(2.0, 0.1)) / light_on_floor(disk(1.0, 0.1));
+        assert!((ratio - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_highlight() {
+        let light_at_mirror_point = |shininess: f64, highlight: Highlight| {
+            let surface = const_surface(BLACK, Color::new(0.2, 0.2, 0.2), Color::new(0.5, 0.5, 0.5), BLACK, 1.0);
+            let floor = Plane::new(
+                Vector3d::new(0.0, 0.0, -1.0),
+                Vector3d::z_axis(),
+                Box::new(surface.with_shininess(shininess, highlight)),
+            )
+            .unwrap();
+            let light = PointLight::new(Vector3d::new(4.0, 0.0, 1.0), WHITE);
+            let world = World::new(Box::new(PerspectiveCamera::default()), vec![Box::new(floor)], vec![Box::new(light)], BLACK, 1);
+            let scene = Scene::new(world);
+            // The light is mirrored by the floor toward the origin at (4/3, 0, -1)
+            let ray = Ray::new(&Vector3d::zero(), &Vector3d::new(4.0 / 3.0, 0.0, -1.0));
+            let intersection = scene.find_intersection(&ray).unwrap();
+            let thing = scene.world().thing(intersection.thing_index());
+            direct_light(&scene, &intersection, thing, &ray, &mut Random::new(1)).r
+        };
+        let matte = light_at_mirror_point(0.0, Highlight::default());
+        for highlight in &[Highlight::Phong, Highlight::BlinnPhong] {
+            assert!((light_at_mirror_point(20.0, *highlight) - matte - 0.5).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_emitted_light() {
+        let scene = soft_shadow_scene(true);
+        // The light is seen from the side of the ball but hidden behind it from below
+        let to_light = Ray::new(&Vector3d::new(0.8, 0.0, 0.0), &Vector3d::z_axis());
+        assert_eq!(emitted_light(&scene, &to_light, scene.find_intersection(&to_light).as_ref()), Some(WHITE));
+        let below = Ray::new(&Vector3d::new(0.0, 0.0, -0.9), &Vector3d::z_axis());
+        assert_eq!(emitted_light(&scene, &below, scene.find_intersection(&below).as_ref()), None);
+    }
+}
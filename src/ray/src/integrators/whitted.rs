@@ -0,0 +1,170 @@
+use crate::{
+    color::{Color, BLACK},
+    intersection::Intersection,
+    ray::Ray,
+    sampling::Random,
+    scene::Scene,
+    things::Thing,
+};
+
+use super::{ambiant_light, direct_light, emitted_light, shading_point, Integrator};
+
+// Remaining recursion of a ray. The glossy surfaces trace several rays until the first
+// glossy bounce then a single ray, so that the number of rays does not grow exponentially.
+#[derive(Clone, Copy)]
+struct Depth {
+    remaining: u16,
+    split_glossy: bool,
+}
+
+impl Depth {
+    fn new(max_recurions: u16) -> Self {
+        Self {
+            remaining: max_recurions,
+            split_glossy: true,
+        }
+    }
+
+    fn is_last(self) -> bool {
+        self.remaining == 0
+    }
+
+    fn next(self) -> Self {
+        Self {
+            remaining: self.remaining - 1,
+            ..self
+        }
+    }
+
+    fn after_glossy(self) -> Self {
+        Self {
+            remaining: self.remaining - 1,
+            split_glossy: false,
+        }
+    }
+}
+
+// Classic recursive ray tracer: direct light from the lights, mirror reflection and refraction,
+// and glossy reflection for the materials having a glossy lobe.
+#[derive(Default)]
+pub struct WhittedIntegrator;
+
+impl WhittedIntegrator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn launch_ray(&self, scene: &Scene, ray: &Ray, random: &mut Random, depth: Depth) -> Color {
+        let intersection = scene.find_intersection(ray);
+        if let Some(emitted) = emitted_light(scene, ray, intersection.as_ref()) {
+            return emitted;
+        }
+        match intersection {
+            Some(inter) => {
+                let thing = scene.world().thing(inter.thing_index());
+
+                let emitted = thing.surface_at(inter.hit()).emission(&shading_point(&inter, thing));
+                let direct = ambiant_light(scene, &inter, thing) + direct_light(scene, &inter, thing, ray, random);
+                emitted
+                    + direct
+                    + self.specular_component(scene, &inter, thing, ray, random, depth)
+                    + self.glossy_component(scene, &inter, thing, ray, random, depth)
+            }
+            None => BLACK,
+        }
+    }
+
+    // Light coming from the perfectly specular directions of the material.
+    fn specular_component(
+        &self,
+        scene: &Scene,
+        intersection: &Intersection,
+        thing: &dyn Thing,
+        ray: &Ray,
+        random: &mut Random,
+        depth: Depth,
+    ) -> Color {
+        if depth.is_last() {
+            return BLACK;
+        }
+        let point = shading_point(intersection, thing);
+        let to_viewer = -ray.dir().clone();
+        thing
+            .surface_at(intersection.hit())
+            .specular_rays(&point, &to_viewer)
+            .iter()
+            .map(|sample| {
+                let new_ray = Ray::new(intersection.position(), &sample.direction);
+                &self.launch_ray(scene, &new_ray, random, depth.next()) * &sample.weight
+            })
+            .sum()
+    }
+
+    // Average of the rays sampled in the glossy lobe of the material.
+    fn glossy_component(
+        &self,
+        scene: &Scene,
+        intersection: &Intersection,
+        thing: &dyn Thing,
+        ray: &Ray,
+        random: &mut Random,
+        depth: Depth,
+    ) -> Color {
+        let material = thing.surface_at(intersection.hit());
+        let samples = material.glossy_samples();
+        if depth.is_last() || samples == 0 {
+            return BLACK;
+        }
+        let point = shading_point(intersection, thing);
+        let to_viewer = -ray.dir().clone();
+        let rays = if depth.split_glossy { samples } else { 1 };
+        let mut total = BLACK;
+        for _ in 0..rays {
+            if let Some(sample) = material.sample_glossy(&point, &to_viewer, random) {
+                let new_ray = Ray::new(intersection.position(), &sample.direction);
+                total = total + &self.launch_ray(scene, &new_ray, random, depth.after_glossy()) * &sample.weight;
+            }
+        }
+        total.scale(1.0 / rays as f64)
+    }
+}
+
+impl Integrator for WhittedIntegrator {
+    fn radiance(&self, ray: &Ray, scene: &Scene, random: &mut Random) -> Color {
+        self.launch_ray(scene, ray, random, Depth::new(scene.world().max_recurions()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::Engine,
+        surfaces::Fresnel,
+        test_worlds::{glass_world, is_finite},
+        vector::Vector3d,
+    };
+
+    #[test]
+    fn test_total_internal_reflection_has_no_nan() {
+        for fresnel in &[None, Some(Fresnel::Schlick), Some(Fresnel::Exact)] {
+            let scene = Scene::new(glass_world(*fresnel));
+            // From the inside of the glass sphere at a grazing angle
+            let ray = Ray::new(&Vector3d::new(3.0, 0.0, 0.45), &Vector3d::new(0.0, 1.0, 0.05));
+            let intersection = scene.find_intersection(&ray).unwrap();
+            assert!(!intersection.collide_from_outside());
+            let thing = scene.world().thing(intersection.thing_index());
+            let mut random = Random::new(1);
+            let whitted = WhittedIntegrator::new();
+            let color = whitted.specular_component(&scene, &intersection, thing, &ray, &mut random, Depth::new(3));
+            assert!(is_finite(&color));
+
+            let image = Engine::new(glass_world(*fresnel)).generate();
+            for y in 0..image.height() {
+                for x in 0..image.width() {
+                    assert!(is_finite(&image.get_color(x, y)));
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use crate::vector::Vector3d;
+use crate::{things::ThingHit, vector::Vector3d};
 
 pub const EPSILON: f64 = 0.000_000_1;
 pub struct Intersection {
-    position: Vector3d,
+    hit: ThingHit,
     thing_index: usize,
     distance: f64,
     normal: Vector3d,
@@ -10,9 +10,9 @@ pub struct Intersection {
 }
 
 impl Intersection {
-    pub fn new(thing_index: usize, position: Vector3d, normal: Vector3d, distance: f64,collide_from_outside: bool) -> Self {
+    pub fn new(thing_index: usize, hit: ThingHit, normal: Vector3d, distance: f64,collide_from_outside: bool) -> Self {
         Self {
-            position,
+            hit,
             thing_index,
             distance,
             normal,
@@ -21,7 +21,12 @@ impl Intersection {
     }
 
     pub fn position(&self) -> &Vector3d {
-        &self.position
+        &self.hit.position
+    }
+
+    // Hit handed back to the thing to shade it.
+    pub fn hit(&self) -> &ThingHit {
+        &self.hit
     }
 
     pub fn normal(&self) -> &Vector3d {
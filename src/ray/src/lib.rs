@@ -1,14 +1,25 @@
 pub mod color;
-pub mod camera;
+pub mod cameras;
 pub mod ray;
 pub mod image;
 pub mod engine;
+pub mod integrators;
+pub mod scene;
+pub mod bvh;
 pub mod world;
 pub mod things;
-pub mod light;
+pub mod lights;
 pub mod surfaces;
 pub mod intersection;
 pub mod vector;
+pub mod encoders;
+pub mod tone_mapping;
+pub mod error;
+pub mod loaders;
+pub mod sampling;
+
+#[cfg(test)]
+mod test_worlds;
 
 #[macro_use]
 extern crate serde_derive;
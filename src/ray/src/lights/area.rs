@@ -0,0 +1,314 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    intersection::EPSILON,
+    ray::Ray,
+    sampling::{orthonormal_basis, Random},
+    vector::Vector3d,
+};
+
+fn default_samples() -> usize {
+    16
+}
+
+// Rectangles and disks only emit light on the side their normal points to,
+// the normal of a rectangle being `edge_u` x `edge_v`. A rectangle with edges that are not
+// perpendicular is the parallelogram they span.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AreaShape {
+    Rectangle {
+        center: Vector3d,
+        edge_u: Vector3d,
+        edge_v: Vector3d,
+    },
+    Disk {
+        center: Vector3d,
+        normal: Vector3d,
+        radius: f64,
+    },
+    Sphere {
+        center: Vector3d,
+        radius: f64,
+    },
+}
+
+// Point on a light seen from a shading point, `solid_angle` is the part of the directions
+// the sample stands for, the inverse of its density: area * emission cosine / distance^2
+// for flat lights, the whole cone under which a sphere is seen.
+pub struct LightSample {
+    pub position: Vector3d,
+    pub solid_angle: f64,
+}
+
+// Light emitted by a shape, sampled with `samples` shadow rays per shading point. The color is
+// the radiance of the shape: the light received falls off with the square of the distance.
+#[derive(Serialize, Deserialize)]
+pub struct AreaLight {
+    shape: AreaShape,
+    color: Color,
+    #[serde(default = "default_samples")]
+    samples: usize,
+}
+
+impl AreaLight {
+    pub fn new(shape: AreaShape, color: Color, samples: usize) -> Self {
+        Self {
+            shape,
+            color,
+            samples: samples.max(1),
+        }
+    }
+
+    pub fn shape(&self) -> &AreaShape {
+        &self.shape
+    }
+
+    pub fn color(&self) -> &Color {
+        &self.color
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples.max(1)
+    }
+
+    // Distance to the emitting side of the light along the ray.
+    pub fn intersect(&self, ray: &Ray) -> Option<f64> {
+        let distance = match &self.shape {
+            AreaShape::Rectangle { center, edge_u, edge_v } => {
+                let normal = edge_u.cross(edge_v.clone()).norm().ok()?;
+                let position = Self::intersect_front(ray, center, &normal)?;
+                // Coordinates of the hit along the edges: offset = u * edge_u + v * edge_v
+                let offset = &position - center;
+                let (uu, uv, vv) = (edge_u.dot(edge_u), edge_u.dot(edge_v), edge_v.dot(edge_v));
+                let (ou, ov) = (offset.dot(edge_u), offset.dot(edge_v));
+                let det = uu * vv - uv * uv;
+                let (u, v) = ((vv * ou - uv * ov) / det, (uu * ov - uv * ou) / det);
+                if u.abs() <= 0.5 && v.abs() <= 0.5 {
+                    Some((&position - ray.start()).mag())
+                } else {
+                    None
+                }
+            }
+            AreaShape::Disk { center, normal, radius } => {
+                let position = Self::intersect_front(ray, center, &normal.norm().ok()?)?;
+                if (&position - center).mag() <= *radius {
+                    Some((&position - ray.start()).mag())
+                } else {
+                    None
+                }
+            }
+            AreaShape::Sphere { center, radius } => {
+                let l = center - ray.start();
+                let adj = l.dot(ray.dir());
+                let d2 = l.dot(&l) - adj * adj;
+                if d2 > radius * radius {
+                    return None;
+                }
+                let thc = (radius * radius - d2).sqrt();
+                [adj - thc, adj + thc].iter().copied().find(|t| *t > EPSILON)
+            }
+        };
+        distance.filter(|distance| *distance > EPSILON)
+    }
+
+    fn intersect_front(ray: &Ray, center: &Vector3d, normal: &Vector3d) -> Option<Vector3d> {
+        let cos = ray.dir().dot(normal);
+        if cos >= 0.0 {
+            return None;
+        }
+        let t = (center - ray.start()).dot(normal) / cos;
+        if t > EPSILON {
+            Some(ray.start() + &ray.dir().each_mul(t))
+        } else {
+            None
+        }
+    }
+
+    // Random point of the light lighting `from`, None if `from` is behind the light.
+    pub fn sample(&self, from: &Vector3d, random: &mut Random) -> Option<LightSample> {
+        let (u1, u2) = (random.next_f64(), random.next_f64());
+        match &self.shape {
+            AreaShape::Rectangle { center, edge_u, edge_v } => {
+                let normal = edge_u.cross(edge_v.clone()).norm().ok()?;
+                let position = &(center + &edge_u.each_mul(u1 - 0.5)) + &edge_v.each_mul(u2 - 0.5);
+                let area = edge_u.cross(edge_v.clone()).mag();
+                Self::flat_sample(position, &normal, area, from)
+            }
+            AreaShape::Disk { center, normal, radius } => {
+                let normal = normal.norm().ok()?;
+                let (tangent, bitangent) = orthonormal_basis(&normal);
+                let (r, phi) = (radius * u1.sqrt(), 2.0 * PI * u2);
+                let position = &(center + &tangent.each_mul(r * phi.cos())) + &bitangent.each_mul(r * phi.sin());
+                Self::flat_sample(position, &normal, PI * radius * radius, from)
+            }
+            AreaShape::Sphere { center, radius } => {
+                // Uniform in the cone of directions under which the sphere is seen
+                let to_center = center - from;
+                let distance = to_center.mag();
+                if distance <= *radius {
+                    return None;
+                }
+                let axis = to_center.each_div(distance);
+                let cos_max = (1.0 - (radius * radius) / (distance * distance)).max(0.0).sqrt();
+                let cos_theta = 1.0 - u1 * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * u2;
+                let (tangent, bitangent) = orthonormal_basis(&axis);
+                let dir = &(&tangent.each_mul(sin_theta * phi.cos()) + &bitangent.each_mul(sin_theta * phi.sin()))
+                    + &axis.each_mul(cos_theta);
+                let t = distance * cos_theta
+                    - (radius * radius - distance * distance * sin_theta * sin_theta).max(0.0).sqrt();
+                Some(LightSample {
+                    position: from + &dir.each_mul(t),
+                    solid_angle: 2.0 * PI * (1.0 - cos_max),
+                })
+            }
+        }
+    }
+
+    // Point picked uniformly on a flat light of area `area`.
+    fn flat_sample(position: Vector3d, normal: &Vector3d, area: f64, from: &Vector3d) -> Option<LightSample> {
+        let to_from = from - &position;
+        let distance2 = to_from.dot(&to_from);
+        let cos = to_from.norm().ok()?.dot(normal);
+        if cos > 0.0 {
+            Some(LightSample {
+                position,
+                solid_angle: area * cos / distance2,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+
+    // Parallelogram facing down, its edges 0.8 off perpendicular
+    fn skewed() -> AreaShape {
+        AreaShape::Rectangle {
+            center: Vector3d::new(0.0, 0.0, 2.0),
+            edge_u: Vector3d::new(0.0, 1.0, 0.0),
+            edge_v: Vector3d::new(1.0, 0.8, 0.0),
+        }
+    }
+
+    fn rectangle() -> AreaLight {
+        let shape = AreaShape::Rectangle {
+            center: Vector3d::new(0.0, 0.0, 2.0),
+            edge_u: Vector3d::new(0.0, 1.0, 0.0),
+            edge_v: Vector3d::new(1.0, 0.0, 0.0),
+        };
+        AreaLight::new(shape, WHITE, 4)
+    }
+
+    #[test]
+    fn test_intersect() {
+        // The rectangle faces down
+        let light = rectangle();
+        let up = Ray::new(&Vector3d::zero(), &Vector3d::z_axis());
+        assert!((light.intersect(&up).unwrap() - 2.0).abs() < 1e-9);
+        let beside = Ray::new(&Vector3d::new(0.6, 0.0, 0.0), &Vector3d::z_axis());
+        assert!(light.intersect(&beside).is_none());
+        let from_above = Ray::new(&Vector3d::new(0.0, 0.0, 4.0), &-Vector3d::z_axis());
+        assert!(light.intersect(&from_above).is_none());
+
+        let disk = AreaShape::Disk {
+            center: Vector3d::new(0.0, 0.0, 2.0),
+            normal: -Vector3d::z_axis(),
+            radius: 0.5,
+        };
+        let disk = AreaLight::new(disk, WHITE, 4);
+        assert!((disk.intersect(&up).unwrap() - 2.0).abs() < 1e-9);
+        let corner = Ray::new(&Vector3d::new(0.45, 0.45, 0.0), &Vector3d::z_axis());
+        assert!(disk.intersect(&corner).is_none());
+        assert!(light.intersect(&corner).is_some());
+
+        let sphere = AreaShape::Sphere {
+            center: Vector3d::new(0.0, 0.0, 2.0),
+            radius: 0.5,
+        };
+        let sphere = AreaLight::new(sphere, WHITE, 4);
+        assert!((sphere.intersect(&up).unwrap() - 1.5).abs() < 1e-9);
+        assert!(sphere.intersect(&from_above).is_some());
+
+        // Inside the rectangle around the parallelogram only
+        let skewed = AreaLight::new(skewed(), WHITE, 4);
+        assert!(skewed.intersect(&Ray::new(&Vector3d::new(0.45, -0.45, 0.0), &Vector3d::z_axis())).is_none());
+        assert!(skewed.intersect(&Ray::new(&Vector3d::new(0.45, 0.45, 0.0), &Vector3d::z_axis())).is_some());
+    }
+
+    #[test]
+    fn test_samples_are_on_the_light() {
+        let mut random = Random::new(3);
+        let from = Vector3d::new(0.3, -0.2, 0.0);
+        let shapes = vec![
+            rectangle().shape().clone(),
+            skewed(),
+            AreaShape::Disk {
+                center: Vector3d::new(0.0, 0.0, 2.0),
+                normal: -Vector3d::z_axis(),
+                radius: 0.5,
+            },
+            AreaShape::Sphere {
+                center: Vector3d::new(0.0, 0.0, 2.0),
+                radius: 0.5,
+            },
+        ];
+        for shape in shapes {
+            let light = AreaLight::new(shape, WHITE, 1);
+            for _ in 0..100 {
+                let sample = light.sample(&from, &mut random).unwrap();
+                assert!(sample.solid_angle > 0.0 && sample.solid_angle < 2.0 * PI);
+                // The ray toward the sample hits the light at the sample
+                let to_sample = &sample.position - &from;
+                let distance = light.intersect(&Ray::new(&from, &to_sample)).unwrap();
+                assert!((distance - to_sample.mag()).abs() < 1e-6);
+            }
+        }
+        // Nothing lit behind a flat light
+        assert!(rectangle().sample(&Vector3d::new(0.0, 0.0, 3.0), &mut random).is_none());
+    }
+
+    #[test]
+    fn test_solid_angle() {
+        // Disk and sphere seen under the same cone from the origin
+        let (distance, radius): (f64, f64) = (2.0, 0.5);
+        let to_edge = (distance * distance + radius * radius).sqrt();
+        let expected = 2.0 * PI * (1.0 - distance / to_edge);
+        let disk = AreaShape::Disk {
+            center: Vector3d::new(0.0, 0.0, distance),
+            normal: -Vector3d::z_axis(),
+            radius,
+        };
+        let sphere = AreaShape::Sphere {
+            center: Vector3d::new(0.0, 0.0, to_edge),
+            radius,
+        };
+        let mut random = Random::new(5);
+        for shape in [disk, sphere] {
+            let light = AreaLight::new(shape, WHITE, 1);
+            let count = 20000;
+            let total: f64 = (0..count)
+                .map(|_| light.sample(&Vector3d::zero(), &mut random).unwrap().solid_angle)
+                .sum();
+            assert!((total / count as f64 - expected).abs() < expected * 0.01);
+        }
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let light: AreaLight = serde_json::from_str(
+            r#"{"shape": {"type": "disk", "center": {"x": 0, "y": 0, "z": 2}, "normal": {"x": 0, "y": 0, "z": -1}, "radius": 1},
+                "color": {"r": 1, "g": 1, "b": 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(light.samples(), 16);
+        assert!(matches!(light.shape(), AreaShape::Disk { .. }));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{color::Color, surfaces::one, vector::Vector3d};
+
+use super::{Illumination, LightSource};
+
+// Light coming from infinitely far (the sun), `direction` is the direction the light travels.
+#[derive(Serialize, Deserialize)]
+pub struct DirectionalLight {
+    direction: Vector3d,
+    color: Color,
+    #[serde(default = "one")]
+    intensity: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector3d, color: Color) -> Self {
+        Self {
+            direction,
+            color,
+            intensity: one(),
+        }
+    }
+
+    pub fn with_intensity(self, intensity: f64) -> Self {
+        Self { intensity, ..self }
+    }
+}
+
+#[typetag::serde(name = "directional")]
+impl LightSource for DirectionalLight {
+    fn illuminate(&self, _position: &Vector3d) -> Option<Illumination> {
+        let direction = (Vector3d::zero() - self.direction.clone()).norm().ok()?;
+        Some(Illumination {
+            direction,
+            distance: f64::INFINITY,
+            color: self.color.scale(self.intensity),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+
+    #[test]
+    fn test_same_light_everywhere() {
+        let sun = DirectionalLight::new(Vector3d::new(0.0, 0.0, -2.0), WHITE).with_intensity(0.5);
+        for position in &[Vector3d::zero(), Vector3d::new(1000.0, -30.0, 5.0)] {
+            let illumination = sun.illuminate(position).unwrap();
+            assert_eq!(illumination.direction, Vector3d::z_axis());
+            assert_eq!(illumination.distance, f64::INFINITY);
+            assert_eq!(illumination.color, Color::new(0.5, 0.5, 0.5));
+        }
+    }
+}
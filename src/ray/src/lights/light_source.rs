@@ -0,0 +1,17 @@
+use crate::{color::Color, vector::Vector3d};
+
+// Light reaching a point from a light source.
+pub struct Illumination {
+    // Unit vector from the point toward the light
+    pub direction: Vector3d,
+    // Infinite for the lights placed infinitely far
+    pub distance: f64,
+    pub color: Color,
+}
+
+// Without a `type` the light is a point light.
+#[typetag::serde(tag = "type", default_variant = "point")]
+pub trait LightSource: Send + Sync {
+    // None when the light does not reach the position.
+    fn illuminate(&self, position: &Vector3d) -> Option<Illumination>;
+}
@@ -0,0 +1,14 @@
+mod light_source;
+pub use light_source::*;
+
+mod point;
+pub use point::*;
+
+mod directional;
+pub use directional::*;
+
+mod spot;
+pub use spot::*;
+
+mod area;
+pub use area::*;
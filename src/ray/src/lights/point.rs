@@ -0,0 +1,134 @@
+use crate::{color::Color, surfaces::one, vector::Vector3d};
+
+use super::{Illumination, LightSource};
+
+// Light divided by `constant + linear * d + quadratic * d²` at distance d,
+// no falloff by default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Attenuation {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::new(1.0, 0.0, 0.0)
+    }
+}
+
+impl Attenuation {
+    pub fn new(constant: f64, linear: f64, quadratic: f64) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    pub fn inverse_square() -> Self {
+        Self::new(0.0, 0.0, 1.0)
+    }
+
+    pub fn factor(&self, distance: f64) -> f64 {
+        let divisor = self.constant + self.linear * distance + self.quadratic * distance * distance;
+        if divisor > f64::EPSILON {
+            1.0 / divisor
+        } else {
+            0.0
+        }
+    }
+}
+
+// Light emitted in all directions from a point.
+#[derive(Serialize, Deserialize)]
+pub struct PointLight {
+    position: Vector3d,
+    color: Color,
+    #[serde(default = "one")]
+    intensity: f64,
+    #[serde(default)]
+    attenuation: Attenuation,
+}
+
+impl PointLight {
+    pub fn new(position: Vector3d, color: Color) -> Self {
+        Self {
+            position,
+            color,
+            intensity: one(),
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    pub fn with_intensity(self, intensity: f64) -> Self {
+        Self { intensity, ..self }
+    }
+
+    pub fn with_attenuation(self, attenuation: Attenuation) -> Self {
+        Self { attenuation, ..self }
+    }
+
+    pub fn position(&self) -> &Vector3d {
+        &self.position
+    }
+
+    pub fn color(&self) -> &Color {
+        &self.color
+    }
+}
+
+#[typetag::serde(name = "point")]
+impl LightSource for PointLight {
+    fn illuminate(&self, position: &Vector3d) -> Option<Illumination> {
+        let to_light = &self.position - position;
+        let distance = to_light.mag();
+        let direction = to_light.norm().ok()?;
+        let color = self.color.scale(self.intensity * self.attenuation.factor(distance));
+        Some(Illumination {
+            direction,
+            distance,
+            color,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+
+    #[test]
+    fn test_attenuation() {
+        let light = PointLight::new(Vector3d::zero(), WHITE);
+        let near = light.illuminate(&Vector3d::new(2.0, 0.0, 0.0)).unwrap();
+        let far = light.illuminate(&Vector3d::new(2000.0, 0.0, 0.0)).unwrap();
+        assert_eq!(near.color, far.color);
+        assert_eq!(near.direction, -Vector3d::x_axis());
+        assert_eq!(near.distance, 2.0);
+
+        let light = light.with_intensity(8.0).with_attenuation(Attenuation::inverse_square());
+        let near = light.illuminate(&Vector3d::new(2.0, 0.0, 0.0)).unwrap();
+        assert_eq!(near.color, Color::new(2.0, 2.0, 2.0));
+        let linear = Attenuation::new(1.0, 0.5, 0.0);
+        assert_eq!(linear.factor(2.0), 0.5);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let light: Box<dyn LightSource> = serde_json::from_str(
+            r#"{"type": "point", "position": {"x": 0, "y": 0, "z": 0}, "color": {"r": 1, "g": 1, "b": 1},
+                "attenuation": {"quadratic": 1}}"#,
+        )
+        .unwrap();
+        let illumination = light.illuminate(&Vector3d::new(0.0, 0.0, 2.0)).unwrap();
+        // Constant part kept at 1 when only the quadratic part is given
+        assert_eq!(illumination.color, WHITE.scale(1.0 / 5.0));
+
+        // Lights without a type are point lights
+        let json = r#"{"position": {"x": 0, "y": 0, "z": 0}, "color": {"r": 1, "g": 1, "b": 1}}"#;
+        let light: Box<dyn LightSource> = serde_json::from_str(json).unwrap();
+        assert_eq!(light.illuminate(&Vector3d::new(0.0, 0.0, 2.0)).unwrap().color, WHITE);
+    }
+}
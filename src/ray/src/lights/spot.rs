@@ -0,0 +1,99 @@
+use crate::{color::Color, surfaces::one, vector::Vector3d};
+
+use super::{Attenuation, Illumination, LightSource};
+
+// Point light restricted to a cone around `direction`: full light up to `inner_angle`
+// from the axis, fading smoothly to nothing at `outer_angle` (half angles in degrees).
+#[derive(Serialize, Deserialize)]
+pub struct SpotLight {
+    position: Vector3d,
+    direction: Vector3d,
+    color: Color,
+    inner_angle: f64,
+    outer_angle: f64,
+    #[serde(default = "one")]
+    intensity: f64,
+    #[serde(default)]
+    attenuation: Attenuation,
+}
+
+impl SpotLight {
+    pub fn new(position: Vector3d, direction: Vector3d, color: Color, inner_angle: f64, outer_angle: f64) -> Self {
+        Self {
+            position,
+            direction,
+            color,
+            inner_angle,
+            outer_angle,
+            intensity: one(),
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    pub fn with_intensity(self, intensity: f64) -> Self {
+        Self { intensity, ..self }
+    }
+
+    pub fn with_attenuation(self, attenuation: Attenuation) -> Self {
+        Self { attenuation, ..self }
+    }
+
+    // 1 inside the inner cone, 0 outside the outer cone and a smoothstep in between.
+    fn cone_factor(&self, cos_angle: f64) -> f64 {
+        let cos_inner = self.inner_angle.to_radians().cos();
+        let cos_outer = self.outer_angle.max(self.inner_angle).to_radians().cos();
+        if cos_angle >= cos_inner {
+            1.0
+        } else if cos_angle <= cos_outer {
+            0.0
+        } else {
+            let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+}
+
+#[typetag::serde(name = "spot")]
+impl LightSource for SpotLight {
+    fn illuminate(&self, position: &Vector3d) -> Option<Illumination> {
+        let to_light = &self.position - position;
+        let distance = to_light.mag();
+        let direction = to_light.norm().ok()?;
+        let axis = self.direction.norm().ok()?;
+        let cone = self.cone_factor(-direction.dot(&axis));
+        if cone <= 0.0 {
+            return None;
+        }
+        let color = self.color.scale(self.intensity * cone * self.attenuation.factor(distance));
+        Some(Illumination {
+            direction,
+            distance,
+            color,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+
+    #[test]
+    fn test_cone() {
+        // Pointing down from z = 1, full light up to 20 degrees and nothing from 40 degrees
+        let spot = SpotLight::new(Vector3d::z_axis(), -Vector3d::z_axis(), WHITE, 20.0, 40.0);
+        let light_at = |x: f64| spot.illuminate(&Vector3d::new(x, 0.0, 0.0)).map(|i| i.color.r).unwrap_or(0.0);
+        assert_eq!(light_at(0.0), 1.0);
+        assert_eq!(light_at(10f64.to_radians().tan()), 1.0);
+        assert_eq!(light_at(50f64.to_radians().tan()), 0.0);
+        assert!(spot.illuminate(&Vector3d::new(0.0, 0.0, 2.0)).is_none());
+
+        // Smooth decrease between the cones
+        let mut previous = 1.0;
+        for degrees in 21..40 {
+            let light = light_at((degrees as f64).to_radians().tan());
+            assert!(light < previous && light > 0.0);
+            previous = light;
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod mtl;
+pub use mtl::*;
+
+mod obj;
+pub use obj::*;
+
+mod texture;
+pub use texture::*;
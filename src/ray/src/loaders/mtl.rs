@@ -0,0 +1,179 @@
+use std::{fs, path::Path};
+
+use crate::{
+    color::{Color, WHITE},
+    error::ObjError,
+    surfaces::{ConstColor, Highlight, Surface},
+};
+
+// http://paulbourke.net/dataformats/mtl/
+struct MtlMaterial {
+    name: String,
+    ambiant: Color,
+    diffuse: Color,
+    specular: Color,
+    refraction_index: f64,
+    dissolve: f64,
+    shininess: f64,
+}
+
+impl MtlMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ambiant: Color::new(0.0, 0.0, 0.0),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::new(0.0, 0.0, 0.0),
+            refraction_index: 1.0,
+            dissolve: 1.0,
+            shininess: 0.0,
+        }
+    }
+
+    // Ka, Kd and Ks map to the ambiant, diffuse and specular colors, the index Ni to the refraction ratio 1 / Ni,
+    // Ns to the shininess and the transparency (1 - d) to the refraction color.
+    fn into_surface(self) -> (String, Surface) {
+        // Most exporters write Ns even without specular color, no highlight then
+        let shininess = if self.specular.is_black() { 0.0 } else { self.shininess };
+        let surface = Surface::new(
+            Box::new(ConstColor::new(self.ambiant)),
+            Box::new(ConstColor::new(self.diffuse)),
+            Box::new(ConstColor::new(self.specular)),
+            Box::new(ConstColor::new(WHITE.scale(1.0 - self.dissolve))),
+            1.0 / self.refraction_index,
+        )
+        .with_shininess(shininess, Highlight::Phong);
+        (self.name, surface)
+    }
+}
+
+fn parse_floats(name: &str, line_number: usize, values: &[&str], count: usize) -> Result<Vec<f64>, ObjError> {
+    if values.len() < count {
+        return Err(ObjError::Parse(
+            name.to_string(),
+            line_number,
+            format!("expected {} values", count),
+        ));
+    }
+    values[..count]
+        .iter()
+        .map(|value| {
+            value.parse::<f64>().map_err(|_| {
+                ObjError::Parse(name.to_string(), line_number, format!("invalid number '{}'", value))
+            })
+        })
+        .collect()
+}
+
+fn parse_color(name: &str, line_number: usize, values: &[&str]) -> Result<Color, ObjError> {
+    // A single value is a grey level
+    let count = if values.len() == 1 { 1 } else { 3 };
+    let rgb = parse_floats(name, line_number, values, count)?;
+    Ok(match rgb.as_slice() {
+        [grey] => Color::new(*grey, *grey, *grey),
+        _ => Color::new(rgb[0], rgb[1], rgb[2]),
+    })
+}
+
+// `name` is only used in error messages.
+pub fn parse_mtl(source: &str, name: &str) -> Result<Vec<(String, Surface)>, ObjError> {
+    let mut materials = Vec::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let tokens: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+        let (keyword, values) = match tokens.split_first() {
+            Some((keyword, values)) => (*keyword, values),
+            None => continue,
+        };
+
+        if keyword == "newmtl" {
+            let material_name = values.join(" ");
+            if material_name.is_empty() {
+                return Err(ObjError::Parse(name.to_string(), line_number, "missing material name".to_string()));
+            }
+            materials.extend(current.replace(MtlMaterial::new(&material_name)));
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some(material) => material,
+            None => {
+                return Err(ObjError::Parse(
+                    name.to_string(),
+                    line_number,
+                    format!("'{}' before any newmtl", keyword),
+                ))
+            }
+        };
+        match keyword {
+            "Ka" => material.ambiant = parse_color(name, line_number, values)?,
+            "Kd" => material.diffuse = parse_color(name, line_number, values)?,
+            "Ks" => material.specular = parse_color(name, line_number, values)?,
+            "Ns" => material.shininess = parse_floats(name, line_number, values, 1)?[0],
+            "Ni" => material.refraction_index = parse_floats(name, line_number, values, 1)?[0],
+            "d" => material.dissolve = parse_floats(name, line_number, values, 1)?[0],
+            "Tr" => material.dissolve = 1.0 - parse_floats(name, line_number, values, 1)?[0],
+            // Other statements (textures, illumination model...) are not supported
+            _ => {}
+        }
+    }
+    materials.extend(current);
+
+    Ok(materials.into_iter().map(MtlMaterial::into_surface).collect())
+}
+
+pub fn load_mtl(path: &Path) -> Result<Vec<(String, Surface)>, ObjError> {
+    let name = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| ObjError::Io(name.clone(), e))?;
+    parse_mtl(&source, &name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{surfaces::TextureContext, vector::Vector2d};
+
+    #[test]
+    fn test_parse_mtl() {
+        let source = "
+            # two materials
+            newmtl red
+            Ka 0.1 0.0 0.0
+            Kd 1.0 0.0 0.0
+            Ks 0.5
+            Ns 32
+            newmtl glass
+            Kd 0.0 0.0 0.0
+            Ns 96
+            Ni 1.5
+            d 0.25
+            illum 7
+        ";
+        let materials = parse_mtl(source, "test.mtl").unwrap();
+        assert_eq!(materials.len(), 2);
+
+        let context = TextureContext::from_uv(Vector2d::new(0.0, 0.0));
+        let (name, red) = &materials[0];
+        assert_eq!(name, "red");
+        assert_eq!(red.ambiant(&context), Color::new(0.1, 0.0, 0.0));
+        assert_eq!(red.diffuse(&context), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(red.specular(&context), Color::new(0.5, 0.5, 0.5));
+        assert!(red.refraction(&context).is_black());
+        assert_eq!(red.shininess(), 32.0);
+        assert_eq!(red.highlight(), Highlight::Phong);
+
+        let (name, glass) = &materials[1];
+        assert_eq!(name, "glass");
+        assert_eq!(glass.refraction_ratio(), 1.0 / 1.5);
+        assert_eq!(glass.refraction(&context), Color::new(0.75, 0.75, 0.75));
+        assert_eq!(glass.shininess(), 0.0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_mtl("Kd 1 0 0", "test.mtl").is_err());
+        assert!(parse_mtl("newmtl a\nKd 1 x 0", "test.mtl").is_err());
+    }
+}
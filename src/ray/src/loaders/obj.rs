@@ -0,0 +1,307 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    error::ObjError,
+    surfaces::{Material, Surface},
+    things::MeshData,
+    vector::{Vector2d, Vector3d},
+};
+
+use super::load_mtl;
+
+// One corner of a face: position, texture coordinate and normal indices (0 based).
+struct FaceVertex {
+    vertex: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+#[derive(Default)]
+struct ObjBuilder {
+    vertices: Vec<Vector3d>,
+    uvs: Vec<Vector2d>,
+    normals: Vec<Vector3d>,
+    indices: Vec<[usize; 3]>,
+    uv_indices: Vec<Option<[usize; 3]>>,
+    normal_indices: Vec<Option<[usize; 3]>>,
+    // Normals computed for the triangles without normals, in triangle order
+    face_normals: Vec<Vector3d>,
+    materials: Vec<Box<dyn Material>>,
+    material_names: HashMap<String, usize>,
+    material_indices: Vec<Option<usize>>,
+    current_material: Option<usize>,
+}
+
+fn parse_error(name: &str, line_number: usize, message: String) -> ObjError {
+    ObjError::Parse(name.to_string(), line_number, message)
+}
+
+fn parse_floats(name: &str, line_number: usize, values: &[&str], min: usize) -> Result<Vec<f64>, ObjError> {
+    if values.len() < min {
+        return Err(parse_error(name, line_number, format!("expected {} values", min)));
+    }
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse::<f64>()
+                .map_err(|_| parse_error(name, line_number, format!("invalid number '{}'", value)))
+        })
+        .collect()
+}
+
+// Obj indices start at 1, negative indices are relative to the end of the buffer.
+fn resolve_index(name: &str, line_number: usize, value: &str, len: usize) -> Result<usize, ObjError> {
+    let index = value
+        .parse::<isize>()
+        .map_err(|_| parse_error(name, line_number, format!("invalid index '{}'", value)))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        len as isize + index
+    };
+    if index == 0 || resolved < 0 || resolved as usize >= len {
+        return Err(parse_error(name, line_number, format!("index {} out of bounds", index)));
+    }
+    Ok(resolved as usize)
+}
+
+impl ObjBuilder {
+    fn parse_face_vertex(&self, name: &str, line_number: usize, token: &str) -> Result<FaceVertex, ObjError> {
+        let mut parts = token.split('/');
+        let vertex = resolve_index(name, line_number, parts.next().unwrap_or(""), self.vertices.len())?;
+        let uv = match parts.next() {
+            Some(uv) if !uv.is_empty() => Some(resolve_index(name, line_number, uv, self.uvs.len())?),
+            _ => None,
+        };
+        let normal = match parts.next() {
+            Some(normal) if !normal.is_empty() => Some(resolve_index(name, line_number, normal, self.normals.len())?),
+            _ => None,
+        };
+        Ok(FaceVertex { vertex, uv, normal })
+    }
+
+    // Polygons are triangulated as a fan around their first vertex.
+    fn add_face(&mut self, corners: &[FaceVertex]) {
+        for i in 1..corners.len() - 1 {
+            let triangle = [&corners[0], &corners[i], &corners[i + 1]];
+            self.indices.push([triangle[0].vertex, triangle[1].vertex, triangle[2].vertex]);
+
+            self.uv_indices.push(match (triangle[0].uv, triangle[1].uv, triangle[2].uv) {
+                (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                _ => None,
+            });
+
+            let normal_indices = match (triangle[0].normal, triangle[1].normal, triangle[2].normal) {
+                (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                _ => {
+                    let [a, b, c] = self.indices[self.indices.len() - 1];
+                    let edge1 = &self.vertices[b] - &self.vertices[a];
+                    let edge2 = &self.vertices[c] - &self.vertices[a];
+                    self.face_normals.push(edge1.cross(edge2).norm().unwrap_or_else(|_| Vector3d::z_axis()));
+                    None
+                }
+            };
+            self.normal_indices.push(normal_indices);
+
+            self.material_indices.push(self.current_material);
+        }
+    }
+
+    // Triangles without uv or normal reference extra entries appended to the buffers
+    // (a (0, 0) uv and their face normal) so that all the triangles stay indexed.
+    fn build(mut self) -> MeshData {
+        let has_uvs = self.uv_indices.iter().any(|uv| uv.is_some());
+        let uv_indices = if has_uvs {
+            let default_uv = self.uvs.len();
+            self.uvs.push(Vector2d::new(0.0, 0.0));
+            Some(self.uv_indices.iter().map(|uv| uv.unwrap_or([default_uv; 3])).collect())
+        } else {
+            self.uvs.clear();
+            None
+        };
+
+        let has_normals = self.normal_indices.iter().any(|normal| normal.is_some());
+        let normal_indices = if has_normals {
+            let mut next_face_normal = self.normals.len();
+            self.normals.append(&mut self.face_normals);
+            Some(
+                self.normal_indices
+                    .iter()
+                    .map(|normal| {
+                        normal.unwrap_or_else(|| {
+                            next_face_normal += 1;
+                            [next_face_normal - 1; 3]
+                        })
+                    })
+                    .collect(),
+            )
+        } else {
+            self.normals.clear();
+            None
+        };
+
+        let has_materials = self.material_indices.iter().any(|material| material.is_some());
+        MeshData {
+            vertices: self.vertices,
+            normals: self.normals,
+            uvs: self.uvs,
+            indices: self.indices,
+            normal_indices,
+            uv_indices,
+            surface: Box::new(Surface::default()),
+            materials: if has_materials { self.materials } else { Vec::new() },
+            material_indices: if has_materials { self.material_indices } else { Vec::new() },
+        }
+    }
+}
+
+// http://paulbourke.net/dataformats/obj/
+// `name` is only used in error messages, `load_materials` is called for each `mtllib` statement.
+pub fn parse_obj<F>(source: &str, name: &str, mut load_materials: F) -> Result<MeshData, ObjError>
+where
+    F: FnMut(&str) -> Result<Vec<(String, Surface)>, ObjError>,
+{
+    let mut builder = ObjBuilder::default();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let tokens: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+        let (keyword, values) = match tokens.split_first() {
+            Some((keyword, values)) => (*keyword, values),
+            None => continue,
+        };
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(name, line_number, values, 3)?;
+                builder.vertices.push(Vector3d::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let vt = parse_floats(name, line_number, values, 1)?;
+                builder.uvs.push(Vector2d::new(vt[0], vt.get(1).cloned().unwrap_or(0.0)));
+            }
+            "vn" => {
+                let vn = parse_floats(name, line_number, values, 3)?;
+                builder.normals.push(Vector3d::new(vn[0], vn[1], vn[2]));
+            }
+            "f" => {
+                if values.len() < 3 {
+                    return Err(parse_error(name, line_number, "a face needs at least 3 vertices".to_string()));
+                }
+                let corners = values
+                    .iter()
+                    .map(|token| builder.parse_face_vertex(name, line_number, token))
+                    .collect::<Result<Vec<FaceVertex>, ObjError>>()?;
+                builder.add_face(&corners);
+            }
+            "mtllib" => {
+                for (material_name, surface) in load_materials(&values.join(" "))? {
+                    builder.material_names.insert(material_name, builder.materials.len());
+                    builder.materials.push(Box::new(surface));
+                }
+            }
+            "usemtl" => {
+                let material_name = values.join(" ");
+                builder.current_material = Some(*builder.material_names.get(&material_name).ok_or_else(|| {
+                    parse_error(name, line_number, format!("unknown material '{}'", material_name))
+                })?);
+            }
+            // Groups, objects and smoothing groups are ignored
+            _ => {}
+        }
+    }
+
+    Ok(builder.build())
+}
+
+// Material libraries are looked up next to the obj file.
+pub fn load_obj(path: &Path) -> Result<MeshData, ObjError> {
+    let name = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| ObjError::Io(name.clone(), e))?;
+    let obj_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_obj(&source, &name, |mtl_file| load_mtl(&obj_dir.join(mtl_file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loaders::parse_mtl;
+
+    fn no_materials(_: &str) -> Result<Vec<(String, Surface)>, ObjError> {
+        Ok(Vec::new())
+    }
+
+    #[test]
+    fn test_parse_quad() {
+        let source = "
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vt 1 0
+            vt 1 1
+            vt 0 1
+            vn 0 0 1
+            f 1/1/1 2/2/1 3/3/1 4/4/1
+        ";
+        let data = parse_obj(source, "quad.obj", no_materials).unwrap();
+        assert_eq!(data.indices, vec!([0, 1, 2], [0, 2, 3]));
+        assert_eq!(data.uv_indices, Some(vec!([0, 1, 2], [0, 2, 3])));
+        assert_eq!(data.normal_indices, Some(vec!([0, 0, 0], [0, 0, 0])));
+        assert_eq!(data.uvs.len(), 5);
+        assert!(data.materials.is_empty());
+    }
+
+    #[test]
+    fn test_parse_without_uvs_and_negative_indices() {
+        let source = "
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            f -3 -2 -1
+        ";
+        let data = parse_obj(source, "tri.obj", no_materials).unwrap();
+        assert_eq!(data.indices, vec!([0, 1, 2]));
+        assert!(data.uvs.is_empty() && data.uv_indices.is_none());
+        assert!(data.normals.is_empty() && data.normal_indices.is_none());
+    }
+
+    #[test]
+    fn test_parse_materials() {
+        let source = "
+            mtllib scene.mtl
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            v 1 1 0
+            f 1 2 3
+            usemtl red
+            f 2//1 4//1 3//1
+            vn 0 0 1
+        ";
+        let data = parse_obj(source, "mat.obj", |file| {
+            assert_eq!(file, "scene.mtl");
+            parse_mtl("newmtl red\nKd 1 0 0", file)
+        });
+        // The normal is declared after the face using it
+        assert!(data.is_err());
+
+        let source = source.replace("vn 0 0 1", "").replace("mtllib scene.mtl", "mtllib scene.mtl\nvn 0 0 1");
+        let data = parse_obj(&source, "mat.obj", |file| parse_mtl("newmtl red\nKd 1 0 0", file)).unwrap();
+        assert_eq!(data.materials.len(), 1);
+        assert_eq!(data.material_indices, vec!(None, Some(0)));
+        assert_eq!(data.normals.len(), 2);
+        // The face normal of the first triangle is appended after the declared normal
+        assert_eq!(data.normal_indices, Some(vec!([1, 1, 1], [0, 0, 0])));
+        assert_eq!(data.normals[1], Vector3d::z_axis());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_obj("v 0 0", "bad.obj", no_materials).is_err());
+        assert!(parse_obj("v 0 0 0\nf 1 2 3", "bad.obj", no_materials).is_err());
+        assert!(parse_obj("usemtl unknown", "bad.obj", no_materials).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use std::{convert::TryFrom, fs, path::Path};
+
+use crate::{color::Color, error::TextureError, image::Image};
+
+// The format is chosen from the file extension, the 8 bits values are mapped to [0, 1].
+pub fn load_texture(path: &Path) -> Result<Image, TextureError> {
+    let name = path.display().to_string();
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    let data = fs::read(path).map_err(|e| TextureError::Io(name.clone(), e))?;
+
+    match extension.as_str() {
+        "png" => decode_png(&data, &name),
+        "ppm" => parse_ppm(&data, &name),
+        _ => Err(TextureError::UnsupportedFormat(name)),
+    }
+}
+
+fn decode_error(name: &str, message: impl ToString) -> TextureError {
+    TextureError::Decode(name.to_string(), message.to_string())
+}
+
+// Image filled pixel by pixel, row 0 is the top of the image.
+fn image_from_samples<F>(name: &str, width: usize, height: usize, mut sample: F) -> Result<Image, TextureError>
+where
+    F: FnMut(usize, usize) -> Color,
+{
+    let too_large = || decode_error(name, format!("image too large ({}x{})", width, height));
+    let mut image = Image::new(
+        u16::try_from(width).map_err(|_| too_large())?,
+        u16::try_from(height).map_err(|_| too_large())?,
+        Color::default(),
+    );
+    for y in 0..height {
+        for x in 0..width {
+            image.set_color(x as u16, y as u16, sample(x, y));
+        }
+    }
+    Ok(image)
+}
+
+// Palette, grey levels and 16 bits images are converted to 8 bits, the alpha channel is ignored.
+pub fn decode_png(data: &[u8], name: &str) -> Result<Image, TextureError> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| decode_error(name, e))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|e| decode_error(name, e))?;
+
+    let channels = info.color_type.samples();
+    let value = |index: usize| buffer[index] as f64 / 255.0;
+    image_from_samples(name, info.width as usize, info.height as usize, |x, y| {
+        let index = y * info.line_size + x * channels;
+        match info.color_type {
+            png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                Color::new(value(index), value(index), value(index))
+            }
+            _ => Color::new(value(index), value(index + 1), value(index + 2)),
+        }
+    })
+}
+
+// Header token of a PPM file, skipping the comments.
+fn next_token<'a>(data: &'a [u8], position: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        match data.get(*position)? {
+            b'#' => {
+                while data.get(*position).is_some_and(|c| *c != b'\n') {
+                    *position += 1;
+                }
+            }
+            c if c.is_ascii_whitespace() => *position += 1,
+            _ => break,
+        }
+    }
+    let start = *position;
+    while data.get(*position).is_some_and(|c| !c.is_ascii_whitespace()) {
+        *position += 1;
+    }
+    Some(&data[start..*position])
+}
+
+// http://netpbm.sourceforge.net/doc/ppm.html
+// Both the ascii (P3) and the binary (P6) formats are read.
+pub fn parse_ppm(data: &[u8], name: &str) -> Result<Image, TextureError> {
+    let mut position = 0;
+    let magic = next_token(data, &mut position).ok_or_else(|| decode_error(name, "empty file"))?;
+    let ascii = match magic {
+        b"P3" => true,
+        b"P6" => false,
+        _ => return Err(decode_error(name, "not a P3 or P6 PPM file")),
+    };
+    let mut header_value = |field: &str| {
+        next_token(data, &mut position)
+            .and_then(|token| std::str::from_utf8(token).ok())
+            .and_then(|token| token.parse::<usize>().ok())
+            .ok_or_else(|| decode_error(name, format!("invalid {}", field)))
+    };
+    let width = header_value("width")?;
+    let height = header_value("height")?;
+    let max_value = header_value("maximum value")?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(decode_error(name, format!("invalid maximum value {}", max_value)));
+    }
+    // The images are indexed with u16, checked before any size computation
+    let max_size = u16::MAX as usize;
+    if width == 0 || height == 0 || width > max_size || height > max_size {
+        return Err(decode_error(name, format!("invalid image size {}x{}", width, height)));
+    }
+
+    let too_large = || decode_error(name, format!("image too large ({}x{})", width, height));
+    let count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(too_large)?;
+    let values: Vec<usize> = if ascii {
+        (0..count)
+            .map(|_| {
+                next_token(data, &mut position)
+                    .and_then(|token| std::str::from_utf8(token).ok())
+                    .and_then(|token| token.parse::<usize>().ok())
+                    .ok_or_else(|| decode_error(name, "invalid or missing pixel value"))
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        // A single whitespace separates the header from the pixels
+        let start = position + 1;
+        let bytes_per_value = if max_value < 256 { 1 } else { 2 };
+        let end = count
+            .checked_mul(bytes_per_value)
+            .and_then(|length| length.checked_add(start))
+            .ok_or_else(too_large)?;
+        let pixels = data
+            .get(start..end)
+            .ok_or_else(|| decode_error(name, "truncated pixel data"))?;
+        pixels
+            .chunks(bytes_per_value)
+            .map(|bytes| bytes.iter().fold(0, |value, byte| (value << 8) | *byte as usize))
+            .collect()
+    };
+
+    let value = |index: usize| values[index].min(max_value) as f64 / max_value as f64;
+    image_from_samples(name, width, height, |x, y| {
+        let index = 3 * (y * width + x);
+        Color::new(value(index), value(index + 1), value(index + 2))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoders::{ImageEncoder, PngEncoder, PpmEncoder};
+
+    fn small_image() -> Image {
+        let mut image = Image::new(2, 2, Color::new(1.0, 0.0, 0.0));
+        image.set_color(1, 0, Color::new(0.0, 1.0, 0.0));
+        image.set_color(0, 1, Color::new(0.0, 0.0, 1.0));
+        image
+    }
+
+    fn assert_same_image(image: &Image, expected: &Image) {
+        assert_eq!((image.width(), image.height()), (expected.width(), expected.height()));
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                assert_eq!(image.get_color(x, y), expected.get_color(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let mut data: Vec<u8> = Vec::new();
+        PngEncoder::new().encode(&small_image(), &mut data).unwrap();
+        assert_same_image(&decode_png(&data, "small.png").unwrap(), &small_image());
+        assert!(decode_png(b"not a png", "bad.png").is_err());
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        for ascii in &[true, false] {
+            let mut data: Vec<u8> = Vec::new();
+            PpmEncoder::new(*ascii).encode(&small_image(), &mut data).unwrap();
+            assert_same_image(&parse_ppm(&data, "small.ppm").unwrap(), &small_image());
+        }
+    }
+
+    #[test]
+    fn test_parse_ppm() {
+        let source = b"P3\n# comment\n2 1\n# another one\n15\n15 0 0  0 15 15\n";
+        let image = parse_ppm(source, "comments.ppm").unwrap();
+        assert_eq!(image.get_color(0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(image.get_color(1, 0), Color::new(0.0, 1.0, 1.0));
+
+        assert!(parse_ppm(b"P3\n2 1\n255\n255 0 0\n", "short.ppm").is_err());
+        assert!(parse_ppm(b"P6\n2 1\n255\n\xff\x00", "truncated.ppm").is_err());
+        assert!(parse_ppm(b"P5\n1 1\n255\n\x00", "grey.ppm").is_err());
+        assert!(parse_ppm(b"P6\n0 1\n255\n", "empty.ppm").is_err());
+        assert!(parse_ppm(b"P6\n65536 1\n255\n", "wide.ppm").is_err());
+        assert!(parse_ppm(b"P6\n18446744073709551615 18446744073709551615\n255\n", "huge.ppm").is_err());
+    }
+}
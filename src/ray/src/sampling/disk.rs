@@ -0,0 +1,65 @@
+use std::f64::consts::PI;
+
+use super::Random;
+
+// Uniform point of the unit disk.
+pub fn uniform_disk(random: &mut Random) -> (f64, f64) {
+    let (u1, u2) = (random.next_f64(), random.next_f64());
+    let (radius, phi) = (u1.sqrt(), 2.0 * PI * u2);
+    (radius * phi.cos(), radius * phi.sin())
+}
+
+// Uniform point of the regular polygon inscribed in the unit circle, with a vertex at
+// `rotation` radians from the x axis: one of the triangles joining the center to an edge
+// is picked, then a point in it.
+pub fn uniform_polygon(sides: u32, rotation: f64, random: &mut Random) -> (f64, f64) {
+    let (u1, u2, u3) = (random.next_f64(), random.next_f64(), random.next_f64());
+    let side = ((u1 * sides as f64) as u32).min(sides - 1);
+    let angle = |index: u32| rotation + 2.0 * PI * index as f64 / sides as f64;
+    let (a, b) = (angle(side), angle(side + 1));
+    // Barycentric coordinates of the two vertices on the edge
+    let root = u2.sqrt();
+    let (wa, wb) = (root * (1.0 - u3), root * u3);
+    (wa * a.cos() + wb * b.cos(), wa * a.sin() + wb * b.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_disk() {
+        let mut random = Random::new(3);
+        let count = 20000;
+        let mut inside_half = 0;
+        for _ in 0..count {
+            let (x, y) = uniform_disk(&mut random);
+            let radius2 = x * x + y * y;
+            assert!(radius2 <= 1.0);
+            if radius2 <= 0.5 {
+                inside_half += 1;
+            }
+        }
+        // Half of the area is within a radius of sqrt(1/2)
+        assert!((inside_half as f64 / count as f64 - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_uniform_polygon() {
+        let mut random = Random::new(5);
+        let count = 20000;
+        let (mut mean_x, mut mean_y) = (0.0, 0.0);
+        for _ in 0..count {
+            // Square with its vertices on the axes: |x| + |y| <= 1
+            let (x, y) = uniform_polygon(4, 0.0, &mut random);
+            assert!(x.abs() + y.abs() <= 1.0 + 1e-12);
+            mean_x += x / count as f64;
+            mean_y += y / count as f64;
+        }
+        assert!(mean_x.abs() < 0.02 && mean_y.abs() < 0.02);
+
+        // Turned by 45 degrees the square has its edges along the axes
+        let (x, y) = uniform_polygon(4, PI / 4.0, &mut random);
+        assert!(x.abs() <= 0.5f64.sqrt() + 1e-12 && y.abs() <= 0.5f64.sqrt() + 1e-12);
+    }
+}
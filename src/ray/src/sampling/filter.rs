@@ -0,0 +1,163 @@
+use std::str::FromStr;
+
+fn default_box_radius() -> f64 {
+    0.5
+}
+
+fn default_tent_radius() -> f64 {
+    1.0
+}
+
+fn default_gaussian_radius() -> f64 {
+    1.5
+}
+
+fn default_gaussian_alpha() -> f64 {
+    2.0
+}
+
+fn default_mitchell_radius() -> f64 {
+    2.0
+}
+
+fn default_mitchell_parameter() -> f64 {
+    1.0 / 3.0
+}
+
+// Reconstruction filter weighting the samples of a pixel according to their
+// distance to the pixel center, the radius is in pixels.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Filter {
+    Box {
+        #[serde(default = "default_box_radius")]
+        radius: f64,
+    },
+    Tent {
+        #[serde(default = "default_tent_radius")]
+        radius: f64,
+    },
+    Gaussian {
+        #[serde(default = "default_gaussian_radius")]
+        radius: f64,
+        #[serde(default = "default_gaussian_alpha")]
+        alpha: f64,
+    },
+    // Mitchell-Netravali cubic, B = C = 1/3 by default
+    Mitchell {
+        #[serde(default = "default_mitchell_radius")]
+        radius: f64,
+        #[serde(default = "default_mitchell_parameter")]
+        b: f64,
+        #[serde(default = "default_mitchell_parameter")]
+        c: f64,
+    },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::Box {
+            radius: default_box_radius(),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "box" => Ok(Filter::default()),
+            "tent" => Ok(Filter::Tent {
+                radius: default_tent_radius(),
+            }),
+            "gaussian" => Ok(Filter::Gaussian {
+                radius: default_gaussian_radius(),
+                alpha: default_gaussian_alpha(),
+            }),
+            "mitchell" => Ok(Filter::Mitchell {
+                radius: default_mitchell_radius(),
+                b: default_mitchell_parameter(),
+                c: default_mitchell_parameter(),
+            }),
+            _ => Err(format!("Unknown filter: {}", name)),
+        }
+    }
+}
+
+// https://www.cs.utexas.edu/~fussell/courses/cs384g-fall2013/lectures/mitchell/Mitchell.pdf
+fn mitchell_1d(x: f64, b: f64, c: f64) -> f64 {
+    let x = x.abs();
+    if x < 1.0 {
+        ((12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x + (6.0 - 2.0 * b)) / 6.0
+    } else if x < 2.0 {
+        ((-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c))
+            / 6.0
+    } else {
+        0.0
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. } => *radius,
+        }
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        match self {
+            Filter::Box { radius } => {
+                if x.abs() <= *radius {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Tent { radius } => (radius - x.abs()).max(0.0),
+            Filter::Gaussian { radius, alpha } => ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0),
+            // The cubic is defined over [-2, 2]
+            Filter::Mitchell { radius, b, c } => mitchell_1d(2.0 * x / radius, *b, *c),
+        }
+    }
+
+    // Weight of a sample at (dx, dy) pixels from the pixel center, may be negative (Mitchell).
+    pub fn evaluate(&self, dx: f64, dy: f64) -> f64 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters() {
+        let names = ["box", "tent", "gaussian", "mitchell"];
+        for filter in names.iter().map(|name| name.parse::<Filter>().unwrap()) {
+            let radius = filter.radius();
+            assert!(filter.evaluate(0.0, 0.0) > 0.0);
+            assert!(filter.evaluate(0.0, 0.0) >= filter.evaluate(radius * 0.5, 0.0));
+            assert!(filter.evaluate(radius * 1.01, 0.0).abs() < 1e-12);
+        }
+        assert!("lanczos".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_mitchell_is_continuous() {
+        let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+        assert!((mitchell_1d(1.0 - 1e-9, b, c) - mitchell_1d(1.0 + 1e-9, b, c)).abs() < 1e-6);
+        assert!(mitchell_1d(2.0 - 1e-9, b, c).abs() < 1e-6);
+        // Negative lobe
+        assert!(mitchell_1d(1.5, b, c) < 0.0);
+    }
+
+    #[test]
+    fn test_serde_defaults() {
+        let filter: Filter = serde_json::from_str(r#"{ "type": "gaussian", "alpha": 3.0 }"#).unwrap();
+        assert_eq!(filter, Filter::Gaussian { radius: 1.5, alpha: 3.0 });
+    }
+}
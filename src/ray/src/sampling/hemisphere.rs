@@ -0,0 +1,58 @@
+use std::f64::consts::PI;
+
+use crate::vector::Vector3d;
+
+use super::Random;
+
+// Two unit vectors forming an orthonormal basis with the unit vector `n`.
+// Duff et al., "Building an Orthonormal Basis, Revisited"
+pub fn orthonormal_basis(n: &Vector3d) -> (Vector3d, Vector3d) {
+    let sign = 1.0f64.copysign(n.z);
+    let a = -1.0 / (sign + n.z);
+    let b = n.x * n.y * a;
+    (
+        Vector3d::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+        Vector3d::new(b, sign + n.y * n.y * a, -n.y),
+    )
+}
+
+// Direction of the hemisphere around the unit vector `normal`, with a density
+// proportional to the cosine to the normal (pdf = cos / PI).
+pub fn cosine_hemisphere(normal: &Vector3d, random: &mut Random) -> Vector3d {
+    let (u1, u2) = (random.next_f64(), random.next_f64());
+    let radius = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    &(&tangent * &(radius * phi.cos()).into()) + &(&bitangent * &(radius * phi.sin()).into())
+        + normal * &(1.0 - u1).max(0.0).sqrt().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orthonormal_basis() {
+        for n in &[Vector3d::z_axis(), -Vector3d::z_axis(), Vector3d::new(1.0, 2.0, -3.0).norm().unwrap()] {
+            let (t, b) = orthonormal_basis(n);
+            assert!(t.dot(n).abs() < 1e-12 && b.dot(n).abs() < 1e-12 && t.dot(&b).abs() < 1e-12);
+            assert!((t.mag() - 1.0).abs() < 1e-12 && (b.mag() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_cosine_hemisphere() {
+        let normal = Vector3d::new(0.0, 1.0, 1.0).norm().unwrap();
+        let mut random = Random::new(7);
+        let count = 20000;
+        let mut mean_cos = 0.0;
+        for _ in 0..count {
+            let dir = cosine_hemisphere(&normal, &mut random);
+            assert!((dir.mag() - 1.0).abs() < 1e-9);
+            assert!(dir.dot(&normal) >= 0.0);
+            mean_cos += dir.dot(&normal) / count as f64;
+        }
+        // E[cos] = 2/3 for a cosine weighted distribution
+        assert!((mean_cos - 2.0 / 3.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,17 @@
+mod random;
+pub use random::*;
+
+mod pattern;
+pub use pattern::*;
+
+mod filter;
+pub use filter::*;
+
+mod sampler;
+pub use sampler::*;
+
+mod hemisphere;
+pub use hemisphere::*;
+
+mod disk;
+pub use disk::*;
@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use super::Random;
+
+// How the sub-pixel sample positions are distributed over the unit square.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplePattern {
+    // Centers of a regular grid
+    Regular,
+    // One random position in each cell of the grid
+    #[default]
+    Jittered,
+    // Halton sequence in bases 2 and 3, randomly shifted for each pixel
+    Halton,
+    // (0, 2) Sobol sequence, randomly scrambled for each pixel
+    Sobol,
+}
+
+impl FromStr for SamplePattern {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "regular" => Ok(SamplePattern::Regular),
+            "jittered" => Ok(SamplePattern::Jittered),
+            "halton" => Ok(SamplePattern::Halton),
+            "sobol" => Ok(SamplePattern::Sobol),
+            _ => Err(format!("Unknown sample pattern: {}", name)),
+        }
+    }
+}
+
+// Smallest grid with at least `count` cells, as square as possible.
+fn grid_size(count: usize) -> (usize, usize) {
+    let columns = (count as f64).sqrt().ceil().max(1.0) as usize;
+    let rows = count.div_ceil(columns);
+    (columns, rows)
+}
+
+fn radical_inverse(mut index: u32, base: u32) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut factor = inv_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * factor;
+        index /= base;
+        factor *= inv_base;
+    }
+    result
+}
+
+// Second dimension of the Sobol sequence, the first one is the bit reversed index.
+// Kollig and Keller, "Efficient Multidimensional Sampling"
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut v: u32 = 1 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 == 1 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+fn to_unit(value: u32) -> f64 {
+    value as f64 / (1u64 << 32) as f64
+}
+
+impl SamplePattern {
+    // `count` positions in [0, 1)².
+    pub fn points(&self, count: usize, random: &mut Random) -> Vec<(f64, f64)> {
+        match self {
+            SamplePattern::Regular | SamplePattern::Jittered => {
+                let (columns, rows) = grid_size(count);
+                (0..count)
+                    .map(|i| {
+                        let (u, v) = match self {
+                            SamplePattern::Regular => (0.5, 0.5),
+                            _ => (random.next_f64(), random.next_f64()),
+                        };
+                        (
+                            ((i % columns) as f64 + u) / columns as f64,
+                            ((i / columns) as f64 + v) / rows as f64,
+                        )
+                    })
+                    .collect()
+            }
+            SamplePattern::Halton => {
+                let (shift_u, shift_v) = (random.next_f64(), random.next_f64());
+                (0..count as u32)
+                    .map(|i| {
+                        (
+                            (radical_inverse(i, 2) + shift_u).fract(),
+                            (radical_inverse(i, 3) + shift_v).fract(),
+                        )
+                    })
+                    .collect()
+            }
+            SamplePattern::Sobol => {
+                let (scramble_u, scramble_v) = (random.next_u32(), random.next_u32());
+                (0..count as u32)
+                    .map(|i| {
+                        (
+                            to_unit(i.reverse_bits() ^ scramble_u),
+                            to_unit(sobol_second_dimension(i) ^ scramble_v),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every cell of a n x n grid contains exactly one of n² points.
+    fn is_stratified(points: &[(f64, f64)], n: usize) -> bool {
+        let mut cells = vec![0; n * n];
+        for (u, v) in points {
+            cells[(v * n as f64) as usize * n + (u * n as f64) as usize] += 1;
+        }
+        cells.iter().all(|count| *count == 1)
+    }
+
+    #[test]
+    fn test_points_in_unit_square() {
+        for pattern in &[SamplePattern::Regular, SamplePattern::Jittered, SamplePattern::Halton, SamplePattern::Sobol] {
+            let points = pattern.points(7, &mut Random::new(1));
+            assert_eq!(points.len(), 7);
+            assert!(points.iter().all(|(u, v)| (0.0..1.0).contains(u) && (0.0..1.0).contains(v)));
+        }
+        assert_eq!(SamplePattern::Regular.points(1, &mut Random::new(1)), vec!((0.5, 0.5)));
+    }
+
+    #[test]
+    fn test_stratification() {
+        assert!(is_stratified(&SamplePattern::Regular.points(16, &mut Random::new(1)), 4));
+        assert!(is_stratified(&SamplePattern::Jittered.points(16, &mut Random::new(2)), 4));
+        // Unscrambled Sobol points are stratified on power of 2 grids
+        let sobol: Vec<(f64, f64)> = (0..16u32)
+            .map(|i| (to_unit(i.reverse_bits()), to_unit(sobol_second_dimension(i))))
+            .collect();
+        assert!(is_stratified(&sobol, 4));
+        // Random digit scrambling keeps the stratification
+        assert!(is_stratified(&SamplePattern::Sobol.points(16, &mut Random::new(3)), 4));
+    }
+
+    #[test]
+    fn test_halton() {
+        assert_eq!(radical_inverse(1, 2), 0.5);
+        assert_eq!(radical_inverse(3, 2), 0.75);
+        assert!((radical_inverse(4, 3) - 4.0 / 9.0).abs() < 1e-12);
+        assert_eq!("halton".parse::<SamplePattern>(), Ok(SamplePattern::Halton));
+        assert!("poisson".parse::<SamplePattern>().is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::color::{Color, BLACK};
+
+use super::{Filter, Random, SamplePattern};
+
+fn default_samples_per_pixel() -> usize {
+    1
+}
+
+// Anti-aliasing settings of the world.
+// Samples of a pixel are spread over the filter support around the pixel center
+// and averaged with the filter weights, each pixel is computed independently.
+// A single sample goes through the upper left corner of the pixel whatever the pattern,
+// where the rays were shot before the supersampling.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sampler {
+    #[serde(default = "default_samples_per_pixel")]
+    pub samples_per_pixel: usize,
+    #[serde(default)]
+    pub pattern: SamplePattern,
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            samples_per_pixel: default_samples_per_pixel(),
+            pattern: SamplePattern::default(),
+            filter: Filter::default(),
+        }
+    }
+}
+
+impl Sampler {
+    pub fn new(samples_per_pixel: usize, pattern: SamplePattern, filter: Filter) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            pattern,
+            filter,
+        }
+    }
+
+    // `radiance` is called with the fractional image coordinates of each sample
+    // and the random stream of the pixel.
+    pub fn render_pixel<F>(&self, x: u16, y: u16, mut radiance: F) -> Color
+    where
+        F: FnMut(f64, f64, &mut Random) -> Color,
+    {
+        let mut random = Random::for_pixel(x, y);
+        if self.samples_per_pixel <= 1 {
+            return radiance(x as f64, y as f64, &mut random);
+        }
+        let radius = self.filter.radius();
+        let points = self.pattern.points(self.samples_per_pixel, &mut random);
+
+        let mut weighted_sum = BLACK;
+        let mut weights = 0.0;
+        let mut sum = BLACK;
+        for (u, v) in points.iter() {
+            let (dx, dy) = ((2.0 * u - 1.0) * radius, (2.0 * v - 1.0) * radius);
+            let color = radiance(x as f64 + 0.5 + dx, y as f64 + 0.5 + dy, &mut random);
+            let weight = self.filter.evaluate(dx, dy);
+            weighted_sum = weighted_sum + color.scale(weight);
+            weights += weight;
+            sum = sum + color;
+        }
+
+        // Negative lobes can cancel the weights out with few samples
+        if weights > f64::EPSILON {
+            weighted_sum.scale(1.0 / weights)
+        } else {
+            sum.scale(1.0 / points.len() as f64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_sample_at_pixel_corner() {
+        let mut sampler = Sampler::new(1, SamplePattern::Regular, "mitchell".parse().unwrap());
+        let mut positions = Vec::new();
+        let color = sampler.render_pixel(3, 7, |x, y, _| {
+            positions.push((x, y));
+            Color::new(0.5, 0.25, 1.0)
+        });
+        assert_eq!(positions, vec!((3.0, 7.0)));
+        assert_eq!(color, Color::new(0.5, 0.25, 1.0));
+
+        // Also with the random patterns and the default settings of the scenes
+        for pattern in &[SamplePattern::Jittered, SamplePattern::Halton, SamplePattern::Sobol] {
+            sampler.pattern = *pattern;
+            sampler.render_pixel(3, 7, |x, y, _| {
+                assert_eq!((x, y), (3.0, 7.0));
+                BLACK
+            });
+        }
+        Sampler::default().render_pixel(3, 7, |x, y, _| {
+            assert_eq!((x, y), (3.0, 7.0));
+            BLACK
+        });
+    }
+
+    #[test]
+    fn test_box_filter_averages_over_the_pixel() {
+        let sampler = Sampler::new(16, SamplePattern::Regular, Filter::default());
+        // Left half of the pixel is white
+        let color = sampler.render_pixel(0, 0, |x, y, _| {
+            assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+            if x < 0.5 {
+                Color::new(1.0, 1.0, 1.0)
+            } else {
+                BLACK
+            }
+        });
+        assert!((color.r - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_serde_defaults() {
+        let sampler: Sampler = serde_json::from_str(r#"{ "samples_per_pixel": 4 }"#).unwrap();
+        assert_eq!(sampler, Sampler::new(4, SamplePattern::Jittered, Filter::default()));
+        let sampler: Sampler =
+            serde_json::from_str(r#"{ "pattern": "sobol", "filter": { "type": "tent" } }"#).unwrap();
+        assert_eq!(sampler.pattern, SamplePattern::Sobol);
+        assert_eq!(sampler.filter.radius(), 1.0);
+    }
+}
@@ -0,0 +1,80 @@
+use std::cmp::Ordering;
+
+use crate::{
+    bvh::Bvh,
+    intersection::{Intersection, EPSILON},
+    lights::AreaLight,
+    ray::Ray,
+    world::World,
+};
+
+// The world with its acceleration structure, ready to be queried by the integrators.
+pub struct Scene {
+    world: World,
+    bvh: Bvh,
+}
+
+impl Scene {
+    pub fn new(world: World) -> Self {
+        let bvh = Bvh::new(world.things());
+        Self { world, bvh }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn bvh(&self) -> &Bvh {
+        &self.bvh
+    }
+
+    pub fn find_intersection(&self, ray: &Ray) -> Option<Intersection> {
+        self.bvh
+            .closest_intersection(ray, |thing_index| self.find_intersection_with(thing_index, ray))
+    }
+
+    // True when nothing is hit along the ray before `distance`.
+    pub fn is_unobstructed(&self, ray: &Ray, distance: f64) -> bool {
+        match self.find_intersection(ray) {
+            Some(obstruction) => obstruction.distance() >= distance,
+            None => true,
+        }
+    }
+
+    // Closest area light hit by the ray with its distance.
+    pub fn find_area_light(&self, ray: &Ray) -> Option<(&AreaLight, f64)> {
+        self.world
+            .area_lights()
+            .iter()
+            .filter_map(|light| light.intersect(ray).map(|distance| (light, distance)))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+    }
+
+    fn find_intersection_with(&self, thing_index: usize, ray: &Ray) -> Option<Intersection> {
+        self.world
+            .thing(thing_index)
+            .intersect(ray)
+            .into_iter()
+            // Create intersection object
+            .map(|hit| {
+                let normal = self.world.thing(thing_index).normal(&hit);
+                let (normal, collide_from_outside) = if normal.dot(ray.dir()) > 0.0 {
+                    (-normal, false)
+                } else {
+                    (normal, true)
+                };
+                let distance = (ray.start() - &hit.position).mag();
+                Intersection::new(thing_index, hit, normal, distance, collide_from_outside)
+            })
+            // Avoid self intersection
+            .filter(|intersection| intersection.distance() > EPSILON)
+            .filter(|intersection| (intersection.position() - ray.start()).dot(ray.dir()) > 0.0)
+            .min_by(|a, b| {
+                if a.distance() < b.distance() {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            })
+    }
+}
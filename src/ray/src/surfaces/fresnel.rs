@@ -0,0 +1,102 @@
+use crate::vector::Vector3d;
+
+// Fraction of the light reflected by a dielectric interface, the rest goes through it.
+// https://www.scratchapixel.com/lessons/3d-basic-rendering/introduction-to-shading/reflection-refraction-fresnel
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fresnel {
+    // Schlick's approximation
+    Schlick,
+    // Fresnel equations for unpolarized light
+    Exact,
+}
+
+// Cosine of the transmitted angle, None on total internal reflection.
+// `eta` is the ratio n1 / n2 of the refraction indices of the incident and transmitted media.
+pub fn cos_transmitted(cos_incident: f64, eta: f64) -> Option<f64> {
+    let sin2_transmitted = eta * eta * (1.0 - cos_incident * cos_incident);
+    if sin2_transmitted > 1.0 {
+        None
+    } else {
+        Some((1.0 - sin2_transmitted).sqrt())
+    }
+}
+
+pub fn specular_direction(ray_dir: &Vector3d, normal: &Vector3d) -> Vector3d {
+    // https://www.scratchapixel.com/lessons/3d-basic-rendering/introduction-to-shading/reflection-refraction-fresnel
+    ray_dir - &(normal * &(2.0 * ray_dir.dot(normal)).into())
+}
+
+// Snell's law, None on total internal reflection.
+pub fn refraction_direction(ray_dir: &Vector3d, normal: &Vector3d, eta: f64) -> Option<Vector3d> {
+    let cos_incident = -normal.dot(ray_dir);
+    cos_transmitted(cos_incident, eta)
+        .map(|cos_transmitted| ray_dir * &eta.into() + normal * &(eta * cos_incident - cos_transmitted).into())
+}
+
+impl Fresnel {
+    // `cos_incident` is the cosine between the normal and the direction to the viewer.
+    pub fn reflectance(&self, cos_incident: f64, eta: f64) -> f64 {
+        let cos_incident = cos_incident.clamp(0.0, 1.0);
+        let cos_transmitted = match cos_transmitted(cos_incident, eta) {
+            Some(cos) => cos,
+            None => return 1.0,
+        };
+        match self {
+            Fresnel::Schlick => {
+                let r0 = ((eta - 1.0) / (eta + 1.0)).powi(2);
+                // Going to a less dense medium, the angle to use is the transmitted one
+                let cos = if eta > 1.0 { cos_transmitted } else { cos_incident };
+                r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+            }
+            Fresnel::Exact => {
+                let rs = (eta * cos_incident - cos_transmitted) / (eta * cos_incident + cos_transmitted);
+                let rp = (cos_incident - eta * cos_transmitted) / (cos_incident + eta * cos_transmitted);
+                (rs * rs + rp * rp) / 2.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_incidence() {
+        // Air to glass reflects 4%
+        for fresnel in &[Fresnel::Schlick, Fresnel::Exact] {
+            assert!((fresnel.reflectance(1.0, 1.0 / 1.5) - 0.04).abs() < 1e-9);
+            assert!((fresnel.reflectance(1.0, 1.5) - 0.04).abs() < 1e-9);
+            assert!(fresnel.reflectance(1.0, 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_grazing_and_total_internal_reflection() {
+        for fresnel in &[Fresnel::Schlick, Fresnel::Exact] {
+            assert!(fresnel.reflectance(0.0, 1.0 / 1.5) > 0.99);
+            assert!(fresnel.reflectance(0.5, 1.0 / 1.5) < fresnel.reflectance(0.1, 1.0 / 1.5));
+            // Critical angle of glass to air is about 41.8°
+            assert_eq!(fresnel.reflectance(0.5, 1.5), 1.0);
+            assert!(fresnel.reflectance(0.9, 1.5) < 1.0);
+        }
+        assert!(cos_transmitted(0.5, 1.5).is_none());
+        assert!((cos_transmitted(0.0, 1.0).unwrap()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_refraction_direction() {
+        let normal = Vector3d::z_axis();
+        let straight = refraction_direction(&-Vector3d::z_axis(), &normal, 1.0 / 1.5).unwrap();
+        assert!((&straight + &Vector3d::z_axis()).mag() < 1e-12);
+
+        // Bends toward the normal going into glass
+        let dir = Vector3d::new(1.0, 0.0, -1.0).norm().unwrap();
+        let refracted = refraction_direction(&dir, &normal, 1.0 / 1.5).unwrap();
+        assert!((refracted.mag() - 1.0).abs() < 1e-12);
+        assert!((refracted.x - (0.5f64.sqrt() / 1.5)).abs() < 1e-12);
+
+        assert!(refraction_direction(&dir, &normal, 1.5).is_none());
+    }
+}
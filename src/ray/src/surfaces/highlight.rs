@@ -0,0 +1,64 @@
+use crate::vector::Vector3d;
+
+// Model of the specular highlights made by the lights on shiny surfaces.
+// https://en.wikipedia.org/wiki/Blinn%E2%80%93Phong_reflection_model
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Highlight {
+    // Angle between the viewer and the mirror direction of the light
+    Phong,
+    // Angle between the normal and the half vector of the light and viewer directions
+    #[default]
+    BlinnPhong,
+}
+
+impl Highlight {
+    // Part of the light reflected toward the viewer, all the vectors are unit vectors
+    // pointing away from the surface.
+    pub fn intensity(&self, normal: &Vector3d, to_light: &Vector3d, to_viewer: &Vector3d, shininess: f64) -> f64 {
+        let cos = match self {
+            Highlight::Phong => {
+                let reflected = &normal.each_mul(2.0 * normal.dot(to_light)) - to_light;
+                reflected.dot(to_viewer)
+            }
+            Highlight::BlinnPhong => match (to_light + to_viewer).norm() {
+                Ok(half) => half.dot(normal),
+                Err(_) => 0.0,
+            },
+        };
+        if cos > 0.0 {
+            cos.powf(shininess)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intensity() {
+        let normal = Vector3d::z_axis();
+        let to_light = Vector3d::new(1.0, 0.0, 1.0).norm().unwrap();
+        let mirror = Vector3d::new(-1.0, 0.0, 1.0).norm().unwrap();
+        let aside = Vector3d::new(-1.0, 0.0, 3.0).norm().unwrap();
+        for highlight in &[Highlight::Phong, Highlight::BlinnPhong] {
+            // Brightest in the mirror direction, fading faster with a higher shininess
+            assert!((highlight.intensity(&normal, &to_light, &mirror, 50.0) - 1.0).abs() < 1e-12);
+            let soft = highlight.intensity(&normal, &to_light, &aside, 10.0);
+            let sharp = highlight.intensity(&normal, &to_light, &aside, 100.0);
+            assert!(soft < 1.0 && sharp < soft && sharp > 0.0);
+            assert_eq!(
+                highlight.intensity(&normal, &to_light, &to_light.each_mul(-1.0), 10.0),
+                0.0
+            );
+        }
+        // The half vector is closer to the normal than the viewer to the mirror direction
+        assert!(
+            Highlight::BlinnPhong.intensity(&normal, &to_light, &aside, 10.0)
+                > Highlight::Phong.intensity(&normal, &to_light, &aside, 10.0)
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    color::{Color, BLACK},
+    error::ResourceError,
+    image::Image,
+    loaders::load_texture,
+};
+
+use super::{ColorAt, TextureContext};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureFilter {
+    // Color of the closest pixel
+    Nearest,
+    // Weighted average of the four closest pixels
+    #[default]
+    Bilinear,
+}
+
+// How the uv coordinates outside [0, 1] are brought back on the image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    // The border pixels are stretched
+    Clamp,
+    // Repeats the image flipped every other time
+    Mirror,
+}
+
+impl WrapMode {
+    fn wrap(&self, index: i64, size: i64) -> usize {
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+        };
+        wrapped as usize
+    }
+}
+
+// PNG or PPM image mapped on the uv coordinates: u goes from the left to the right of the
+// image and v from the bottom to the top.
+#[derive(Serialize, Deserialize)]
+pub struct ImageTexture {
+    // Relative to the scene file directory
+    path: PathBuf,
+    #[serde(default)]
+    filter: TextureFilter,
+    #[serde(default)]
+    wrap: WrapMode,
+    #[serde(skip)]
+    image: Option<Image>,
+}
+
+impl ImageTexture {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            filter: TextureFilter::default(),
+            wrap: WrapMode::default(),
+            image: None,
+        }
+    }
+
+    // Texture of an image already in memory, nothing is loaded with the resources.
+    pub fn from_image(image: Image) -> Self {
+        Self {
+            image: Some(image),
+            ..Self::new(PathBuf::new())
+        }
+    }
+
+    pub fn with_filter(self, filter: TextureFilter) -> Self {
+        Self { filter, ..self }
+    }
+
+    pub fn with_wrap(self, wrap: WrapMode) -> Self {
+        Self { wrap, ..self }
+    }
+
+    // Pixel at the given integer coordinates, row 0 being the bottom of the texture.
+    fn texel(&self, image: &Image, x: i64, y: i64) -> Color {
+        let (width, height) = (image.width() as i64, image.height() as i64);
+        let x = self.wrap.wrap(x, width);
+        let y = self.wrap.wrap(y, height);
+        image.get_color(x as u16, (height as usize - 1 - y) as u16)
+    }
+}
+
+#[typetag::serde(name = "image_texture")]
+impl ColorAt for ImageTexture {
+    fn color(&self, context: &TextureContext) -> Color {
+        // Black until the image is loaded with the world resources
+        let image = match &self.image {
+            Some(image) => image,
+            None => return BLACK,
+        };
+        let uv = &context.uv;
+        // Position in pixels, the pixel centers being at half integers
+        let x = uv.x * image.width() as f64;
+        let y = uv.y * image.height() as f64;
+        match self.filter {
+            TextureFilter::Nearest => self.texel(image, x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (tx, ty) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let bottom = self.texel(image, x0, y0).scale(1.0 - tx) + self.texel(image, x0 + 1, y0).scale(tx);
+                let top = self.texel(image, x0, y0 + 1).scale(1.0 - tx) + self.texel(image, x0 + 1, y0 + 1).scale(tx);
+                bottom.scale(1.0 - ty) + top.scale(ty)
+            }
+        }
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        if self.image.is_none() {
+            self.image = Some(load_texture(&scene_dir.join(&self.path))?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::WHITE, vector::Vector2d};
+
+    // Black and white 2x1 image, black on the left
+    fn texture(filter: TextureFilter, wrap: WrapMode) -> ImageTexture {
+        let mut image = Image::new(2, 1, BLACK);
+        image.set_color(1, 0, WHITE);
+        ImageTexture::from_image(image).with_filter(filter).with_wrap(wrap)
+    }
+
+    fn grey_at(texture: &ImageTexture, u: f64) -> f64 {
+        texture.color(&TextureContext::from_uv(Vector2d::new(u, 0.5))).r
+    }
+
+    #[test]
+    fn test_nearest() {
+        let texture = texture(TextureFilter::Nearest, WrapMode::Repeat);
+        assert_eq!(grey_at(&texture, 0.2), 0.0);
+        assert_eq!(grey_at(&texture, 0.7), 1.0);
+        assert_eq!(grey_at(&texture, 1.2), 0.0);
+        assert_eq!(grey_at(&texture, -0.2), 1.0);
+    }
+
+    #[test]
+    fn test_bilinear() {
+        let texture = texture(TextureFilter::Bilinear, WrapMode::Clamp);
+        assert_eq!(grey_at(&texture, 0.25), 0.0);
+        assert!((grey_at(&texture, 0.5) - 0.5).abs() < 1e-9);
+        assert!((grey_at(&texture, 0.375) - 0.25).abs() < 1e-9);
+        assert_eq!(grey_at(&texture, 0.75), 1.0);
+        assert_eq!(grey_at(&texture, 0.0), 0.0);
+        assert_eq!(grey_at(&texture, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_wrap_modes() {
+        assert_eq!(grey_at(&texture(TextureFilter::Nearest, WrapMode::Clamp), 1.7), 1.0);
+        assert_eq!(grey_at(&texture(TextureFilter::Nearest, WrapMode::Clamp), -0.7), 0.0);
+        // The mirrored copy on the right of the image starts with the white pixel
+        assert_eq!(grey_at(&texture(TextureFilter::Nearest, WrapMode::Mirror), 1.2), 1.0);
+        assert_eq!(grey_at(&texture(TextureFilter::Nearest, WrapMode::Mirror), 1.7), 0.0);
+        assert_eq!(grey_at(&texture(TextureFilter::Nearest, WrapMode::Repeat), 1.7), 1.0);
+    }
+
+    #[test]
+    fn test_rows_from_the_bottom() {
+        let mut image = Image::new(1, 2, BLACK);
+        image.set_color(0, 0, WHITE);
+        let texture = ImageTexture::from_image(image).with_filter(TextureFilter::Nearest);
+        assert_eq!(texture.color(&TextureContext::from_uv(Vector2d::new(0.5, 0.9))), WHITE);
+        assert_eq!(texture.color(&TextureContext::from_uv(Vector2d::new(0.5, 0.1))), BLACK);
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let json = r#"{ "type": "image_texture", "path": "earth.png", "filter": "nearest", "wrap": "mirror" }"#;
+        let mut texture: Box<dyn ColorAt> = serde_json::from_str(json).unwrap();
+        assert_eq!(texture.color(&TextureContext::from_uv(Vector2d::new(0.5, 0.5))), BLACK);
+        assert!(texture.load_resources(Path::new("/nonexistent")).is_err());
+    }
+}
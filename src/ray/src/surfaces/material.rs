@@ -0,0 +1,136 @@
+use std::path::Path;
+
+use crate::{
+    color::{Color, BLACK},
+    error::ResourceError,
+    sampling::Random,
+    vector::Vector3d,
+};
+
+use super::{ColorAt, TextureContext};
+
+// Point of a thing hit by a ray, as seen by its material.
+pub struct ShadingPoint {
+    pub texture: TextureContext,
+    // Unit normal on the side of the ray origin
+    pub normal: Vector3d,
+    // False when the ray comes from the inside of the thing
+    pub from_outside: bool,
+}
+
+// Direction leaving the surface with the weight of the light coming from it:
+// BSDF * cosine / pdf, or the part of the light following a perfectly specular direction.
+pub struct BsdfSample {
+    pub direction: Vector3d,
+    pub weight: Color,
+    // Mirror and refraction directions that the light sampling can not find
+    pub specular: bool,
+}
+
+// How the light is scattered by a surface. The directions are unit vectors pointing
+// away from the surface, `to_viewer` being the opposite of the incoming ray.
+// Scenes without a `type` get the historical four channels surface.
+#[typetag::serde(tag = "type", default_variant = "legacy_phong")]
+pub trait Material: Send + Sync {
+    // BSDF for the light coming from `to_light` and leaving toward `to_viewer`.
+    fn evaluate(&self, point: &ShadingPoint, to_viewer: &Vector3d, to_light: &Vector3d) -> Color;
+
+    // Density of the non specular directions returned by `sample`.
+    fn pdf(&self, point: &ShadingPoint, to_viewer: &Vector3d, to_light: &Vector3d) -> f64;
+
+    // Direction the light reaching `to_viewer` comes from, picked proportionally to the BSDF.
+    fn sample(&self, point: &ShadingPoint, to_viewer: &Vector3d, random: &mut Random) -> Option<BsdfSample>;
+
+    fn emission(&self, _point: &ShadingPoint) -> Color {
+        BLACK
+    }
+
+    // Part of the ambiant light of the world sent back.
+    fn ambiant(&self, _point: &ShadingPoint) -> Color {
+        BLACK
+    }
+
+    // Color of the surface without lighting.
+    fn albedo(&self, point: &ShadingPoint) -> Color;
+
+    // Perfectly specular directions followed by the Whitted integrator.
+    fn specular_rays(&self, _point: &ShadingPoint, _to_viewer: &Vector3d) -> Vec<BsdfSample> {
+        Vec::new()
+    }
+
+    // Number of glossy rays traced by the Whitted integrator at the first glossy bounce, 0 when
+    // the material is not glossy.
+    fn glossy_samples(&self) -> usize {
+        0
+    }
+
+    // Direction of the glossy lobe of the material.
+    fn sample_glossy(&self, _point: &ShadingPoint, _to_viewer: &Vector3d, _random: &mut Random) -> Option<BsdfSample> {
+        None
+    }
+
+    // Loads the textures, paths are relative to the scene file directory.
+    fn load_resources(&mut self, _scene_dir: &Path) -> Result<(), ResourceError> {
+        Ok(())
+    }
+}
+
+// Surface glowing with its own light, seen by the camera rays and found by the random paths
+// of the path integrator. It does not reflect any light.
+#[derive(Serialize, Deserialize)]
+pub struct Emissive {
+    color: Box<dyn ColorAt>,
+}
+
+impl Emissive {
+    pub fn new(color: Box<dyn ColorAt>) -> Self {
+        Self { color }
+    }
+}
+
+#[typetag::serde(name = "emissive")]
+impl Material for Emissive {
+    fn evaluate(&self, _point: &ShadingPoint, _to_viewer: &Vector3d, _to_light: &Vector3d) -> Color {
+        BLACK
+    }
+
+    fn pdf(&self, _point: &ShadingPoint, _to_viewer: &Vector3d, _to_light: &Vector3d) -> f64 {
+        0.0
+    }
+
+    fn sample(&self, _point: &ShadingPoint, _to_viewer: &Vector3d, _random: &mut Random) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emission(&self, point: &ShadingPoint) -> Color {
+        self.color.color(&point.texture)
+    }
+
+    fn albedo(&self, point: &ShadingPoint) -> Color {
+        self.color.color(&point.texture)
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.color.load_resources(scene_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Vector2d;
+
+    #[test]
+    fn test_emissive() {
+        let json = r#"{ "type": "emissive", "color": { "type": "const_color", "color": { "r": 2.0, "g": 1.0, "b": 0.5 } } }"#;
+        let material: Box<dyn Material> = serde_json::from_str(json).unwrap();
+        let point = ShadingPoint {
+            texture: TextureContext::from_uv(Vector2d::new(0.5, 0.5)),
+            normal: Vector3d::z_axis(),
+            from_outside: true,
+        };
+        assert_eq!(material.emission(&point), Color::new(2.0, 1.0, 0.5));
+        assert!(material.sample(&point, &Vector3d::z_axis(), &mut Random::new(1)).is_none());
+        assert!(material.specular_rays(&point, &Vector3d::z_axis()).is_empty());
+    }
+}
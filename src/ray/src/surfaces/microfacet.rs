@@ -0,0 +1,458 @@
+use std::{f64::consts::PI, path::Path};
+
+use crate::{
+    color::{Color, BLACK},
+    error::ResourceError,
+    sampling::{cosine_hemisphere, orthonormal_basis, Random},
+    vector::Vector3d,
+};
+
+use super::{refraction_direction, BsdfSample, ColorAt, ConstColor, Fresnel, Material, ShadingPoint};
+
+// Below this roughness the distribution is too peaked for floating point numbers
+const MIN_ALPHA: f64 = 1e-3;
+
+fn default_samples() -> usize {
+    8
+}
+
+fn opaque() -> Box<dyn ColorAt> {
+    Box::new(ConstColor::new(BLACK))
+}
+
+// Glass, reflecting 4% of the light facing the viewer
+fn default_refraction_ratio() -> f64 {
+    1.0 / 1.5
+}
+
+// Physically based material: a GGX (Trowbridge-Reitz) specular lobe with Smith masking
+// over a lambertian base, both driven by the metallic parameter (metals have no diffuse
+// part and a specular color, dielectrics such as plastics have a white specular coat).
+// The transmission turns the base of the dielectrics into a rough glass refracting the light
+// through the same microfacets.
+// The roughness, metallic and transmission textures are read as grey levels.
+// https://www.cs.cornell.edu/~srm/publications/EGSR07-btdf.pdf
+#[derive(Serialize, Deserialize)]
+pub struct Microfacet {
+    base_color: Box<dyn ColorAt>,
+    roughness: Box<dyn ColorAt>,
+    metallic: Box<dyn ColorAt>,
+    #[serde(default = "opaque")]
+    transmission: Box<dyn ColorAt>,
+    // Index outside over index inside, like the refraction ratio of the legacy surfaces
+    #[serde(default = "default_refraction_ratio")]
+    refraction_ratio: f64,
+    // Glossy rays traced at the first glossy bounce of the Whitted integrator
+    #[serde(default = "default_samples")]
+    samples: usize,
+}
+
+// Parameters of the material at one point.
+struct Lobes {
+    base_color: Color,
+    alpha: f64,
+    metallic: f64,
+    transmission: f64,
+    // n1 / n2 from the side of the viewer to the other side
+    eta: f64,
+}
+
+impl Lobes {
+    // Fresnel reflectance, Schlick's approximation with the total internal reflection for the
+    // dielectrics and the base color facing the viewer for the metals
+    fn fresnel(&self, cos: f64) -> Color {
+        let dielectric = self.dielectric_fresnel(cos);
+        let k = (1.0 - cos.clamp(0.0, 1.0)).powi(5);
+        let schlick = |base: f64| dielectric + (base + (1.0 - base) * k - dielectric) * self.metallic;
+        Color::new(schlick(self.base_color.r), schlick(self.base_color.g), schlick(self.base_color.b))
+    }
+
+    fn dielectric_fresnel(&self, cos: f64) -> f64 {
+        Fresnel::Schlick.reflectance(cos, self.eta)
+    }
+
+    // Distribution of the microfacet normals
+    fn distribution(&self, cos_h: f64) -> f64 {
+        let alpha2 = self.alpha * self.alpha;
+        let d = cos_h * cos_h * (alpha2 - 1.0) + 1.0;
+        alpha2 / (PI * d * d)
+    }
+
+    // Smith masking for one direction
+    fn masking(&self, cos: f64) -> f64 {
+        let alpha2 = self.alpha * self.alpha;
+        2.0 * cos / (cos + (alpha2 + (1.0 - alpha2) * cos * cos).sqrt())
+    }
+
+    // Probabilities to sample the specular reflection and the transmission, the diffuse
+    // lobe takes the rest.
+    fn probabilities(&self) -> (f64, f64) {
+        let specular = 0.5 * (1.0 + self.metallic);
+        (specular, (1.0 - specular) * self.transmission)
+    }
+}
+
+// Microfacet normal between the direction to the viewer and a direction on the other side,
+// None when they can not be linked by a refraction.
+fn transmission_half(lobes: &Lobes, normal: &Vector3d, to_viewer: &Vector3d, to_light: &Vector3d) -> Option<Vector3d> {
+    // Generalized half vector -(n_o o + n_i i) turned to the side of the normal, the index on
+    // the side of the viewer being 1
+    let half = (to_viewer + &to_light.each_mul(1.0 / lobes.eta)).norm().ok()?;
+    let half = if half.dot(normal) < 0.0 { -half } else { half };
+    if to_viewer.dot(&half) > 0.0 && to_light.dot(&half) < 0.0 {
+        Some(half)
+    } else {
+        None
+    }
+}
+
+impl Microfacet {
+    pub fn new(base_color: Box<dyn ColorAt>, roughness: Box<dyn ColorAt>, metallic: Box<dyn ColorAt>) -> Self {
+        Self {
+            base_color,
+            roughness,
+            metallic,
+            transmission: opaque(),
+            refraction_ratio: default_refraction_ratio(),
+            samples: default_samples(),
+        }
+    }
+
+    pub fn with_samples(self, samples: usize) -> Self {
+        Self { samples, ..self }
+    }
+
+    pub fn with_transmission(self, transmission: Box<dyn ColorAt>, refraction_ratio: f64) -> Self {
+        Self {
+            transmission,
+            refraction_ratio,
+            ..self
+        }
+    }
+
+    fn lobes(&self, point: &ShadingPoint) -> Lobes {
+        let texture = &point.texture;
+        let roughness = self.roughness.color(texture).average().clamp(0.0, 1.0);
+        Lobes {
+            base_color: self.base_color.color(texture),
+            alpha: (roughness * roughness).max(MIN_ALPHA),
+            metallic: self.metallic.color(texture).average().clamp(0.0, 1.0),
+            transmission: self.transmission.color(texture).average().clamp(0.0, 1.0),
+            eta: if point.from_outside {
+                self.refraction_ratio
+            } else {
+                1.0 / self.refraction_ratio
+            },
+        }
+    }
+
+    fn specular_pdf(lobes: &Lobes, normal: &Vector3d, to_viewer: &Vector3d, half: &Vector3d) -> f64 {
+        let cos_h = normal.dot(half);
+        lobes.distribution(cos_h) * cos_h / (4.0 * to_viewer.dot(half).abs())
+    }
+
+    // Density of the microfacet normal times the jacobian of the refraction
+    fn transmission_pdf(lobes: &Lobes, normal: &Vector3d, to_viewer: &Vector3d, to_light: &Vector3d) -> f64 {
+        let half = match transmission_half(lobes, normal, to_viewer, to_light) {
+            Some(half) => half,
+            None => return 0.0,
+        };
+        let (cos_oh, cos_ih) = (to_viewer.dot(&half), to_light.dot(&half));
+        let denom = cos_oh + cos_ih / lobes.eta;
+        let cos_h = normal.dot(&half);
+        lobes.distribution(cos_h) * cos_h * -cos_ih / (lobes.eta * lobes.eta * denom * denom)
+    }
+
+    // Refraction through the rough interface for a light on the other side, the radiance
+    // being compressed by (n1 / n2)^2 like in the perfectly specular case
+    fn transmission(lobes: &Lobes, normal: &Vector3d, to_viewer: &Vector3d, to_light: &Vector3d) -> Color {
+        let half = match transmission_half(lobes, normal, to_viewer, to_light) {
+            Some(half) => half,
+            None => return BLACK,
+        };
+        let (cos_o, cos_i) = (normal.dot(to_viewer), -normal.dot(to_light));
+        let (cos_oh, cos_ih) = (to_viewer.dot(&half), to_light.dot(&half));
+        let denom = cos_oh + cos_ih / lobes.eta;
+        let btdf = (1.0 - lobes.dielectric_fresnel(cos_oh))
+            * lobes.distribution(normal.dot(&half))
+            * lobes.masking(cos_o)
+            * lobes.masking(cos_i)
+            * cos_oh
+            * -cos_ih
+            / (cos_o * cos_i * denom * denom);
+        lobes.base_color.scale((1.0 - lobes.metallic) * lobes.transmission * btdf)
+    }
+
+    // Microfacet normal drawn from the distribution
+    fn sample_half(lobes: &Lobes, normal: &Vector3d, random: &mut Random) -> Vector3d {
+        let (u1, u2) = (random.next_f64(), random.next_f64());
+        let tan2 = lobes.alpha * lobes.alpha * u1 / (1.0 - u1).max(f64::EPSILON);
+        let cos_theta = 1.0 / (1.0 + tan2).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        &(&tangent.each_mul(sin_theta * phi.cos()) + &bitangent.each_mul(sin_theta * phi.sin()))
+            + &normal.each_mul(cos_theta)
+    }
+
+    fn reflect(to_viewer: &Vector3d, half: &Vector3d) -> Vector3d {
+        &half.each_mul(2.0 * to_viewer.dot(half)) - to_viewer
+    }
+
+    fn refract(lobes: &Lobes, to_viewer: &Vector3d, half: &Vector3d) -> Option<Vector3d> {
+        refraction_direction(&-to_viewer.clone(), half, lobes.eta)
+    }
+}
+
+#[typetag::serde(name = "microfacet")]
+impl Material for Microfacet {
+    fn evaluate(&self, point: &ShadingPoint, to_viewer: &Vector3d, to_light: &Vector3d) -> Color {
+        let normal = &point.normal;
+        let (cos_o, cos_i) = (normal.dot(to_viewer), normal.dot(to_light));
+        if cos_o <= 0.0 || cos_i == 0.0 {
+            return BLACK;
+        }
+        let lobes = self.lobes(point);
+        if cos_i < 0.0 {
+            return Self::transmission(&lobes, normal, to_viewer, to_light);
+        }
+        let half = match (to_viewer + to_light).norm() {
+            Ok(half) => half,
+            Err(_) => return BLACK,
+        };
+        let cos_oh = to_viewer.dot(&half);
+        let fresnel = lobes.fresnel(cos_oh);
+        let specular = lobes.distribution(normal.dot(&half)) * lobes.masking(cos_o) * lobes.masking(cos_i)
+            / (4.0 * cos_o * cos_i);
+        // The light not reflected by the coat reaches the base
+        let base = (1.0 - lobes.metallic) * (1.0 - lobes.transmission) * (1.0 - lobes.dielectric_fresnel(cos_oh));
+        let diffuse = lobes.base_color.scale(base / PI);
+        diffuse + fresnel.scale(specular)
+    }
+
+    fn pdf(&self, point: &ShadingPoint, to_viewer: &Vector3d, to_light: &Vector3d) -> f64 {
+        let normal = &point.normal;
+        let cos_i = normal.dot(to_light);
+        if normal.dot(to_viewer) <= 0.0 || cos_i == 0.0 {
+            return 0.0;
+        }
+        let lobes = self.lobes(point);
+        let (p_specular, p_transmission) = lobes.probabilities();
+        if cos_i < 0.0 {
+            return p_transmission * Self::transmission_pdf(&lobes, normal, to_viewer, to_light);
+        }
+        let half = match (to_viewer + to_light).norm() {
+            Ok(half) => half,
+            Err(_) => return 0.0,
+        };
+        let p_diffuse = 1.0 - p_specular - p_transmission;
+        p_specular * Self::specular_pdf(&lobes, normal, to_viewer, &half) + p_diffuse * cos_i / PI
+    }
+
+    // Picks the specular, transmission or diffuse lobe then a direction in it.
+    fn sample(&self, point: &ShadingPoint, to_viewer: &Vector3d, random: &mut Random) -> Option<BsdfSample> {
+        let normal = &point.normal;
+        let lobes = self.lobes(point);
+        let (p_specular, p_transmission) = lobes.probabilities();
+        let choice = random.next_f64();
+        let transmitted = choice >= p_specular && choice < p_specular + p_transmission;
+        let direction = if choice < p_specular {
+            Self::reflect(to_viewer, &Self::sample_half(&lobes, normal, random))
+        } else if transmitted {
+            // Nothing goes through on total internal reflection
+            Self::refract(&lobes, to_viewer, &Self::sample_half(&lobes, normal, random))?
+        } else {
+            cosine_hemisphere(normal, random)
+        };
+        // The directions on the wrong side of the surface for their lobe are lost
+        let cos_i = normal.dot(&direction);
+        let pdf = self.pdf(point, to_viewer, &direction);
+        if cos_i == 0.0 || (cos_i < 0.0) != transmitted || pdf <= 0.0 {
+            return None;
+        }
+        let weight = self.evaluate(point, to_viewer, &direction).scale(cos_i.abs() / pdf);
+        Some(BsdfSample {
+            direction,
+            weight,
+            specular: false,
+        })
+    }
+
+    // Reflected or transmitted direction, the diffuse base being lit by the lights only.
+    fn sample_glossy(&self, point: &ShadingPoint, to_viewer: &Vector3d, random: &mut Random) -> Option<BsdfSample> {
+        let normal = &point.normal;
+        let lobes = self.lobes(point);
+        let (p_specular, p_transmission) = lobes.probabilities();
+        let p_refract = p_transmission / (p_specular + p_transmission);
+        let refract = p_refract > 0.0 && random.next_f64() < p_refract;
+        let half = Self::sample_half(&lobes, normal, random);
+        let direction = if refract {
+            Self::refract(&lobes, to_viewer, &half)?
+        } else {
+            Self::reflect(to_viewer, &half)
+        };
+        let (cos_o, cos_i, cos_h) = (normal.dot(to_viewer), normal.dot(&direction), normal.dot(&half));
+        let cos_oh = to_viewer.dot(&half);
+        if cos_o <= 0.0 || cos_h <= 0.0 || cos_oh <= 0.0 {
+            return None;
+        }
+        let weight = if refract {
+            if cos_i >= 0.0 {
+                return None;
+            }
+            // BTDF * cos_i / (D * cos_h * jacobian)
+            let transmitted = (1.0 - lobes.dielectric_fresnel(cos_oh))
+                * lobes.masking(cos_o)
+                * lobes.masking(-cos_i)
+                * cos_oh
+                * lobes.eta
+                * lobes.eta
+                / (cos_o * cos_h);
+            lobes.base_color.scale((1.0 - lobes.metallic) * lobes.transmission * transmitted / p_refract)
+        } else {
+            if cos_i <= 0.0 {
+                return None;
+            }
+            // D * G * F / (4 cos_o cos_i) * cos_i / (D * cos_h / (4 cos_oh))
+            lobes
+                .fresnel(cos_oh)
+                .scale(lobes.masking(cos_o) * lobes.masking(cos_i) * cos_oh / (cos_o * cos_h * (1.0 - p_refract)))
+        };
+        Some(BsdfSample {
+            direction,
+            weight,
+            specular: false,
+        })
+    }
+
+    fn albedo(&self, point: &ShadingPoint) -> Color {
+        self.base_color.color(&point.texture)
+    }
+
+    fn glossy_samples(&self) -> usize {
+        self.samples.max(1)
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.base_color.load_resources(scene_dir)?;
+        self.roughness.load_resources(scene_dir)?;
+        self.metallic.load_resources(scene_dir)?;
+        self.transmission.load_resources(scene_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{surfaces::TextureContext, vector::Vector2d};
+
+    fn grey(value: f64) -> Box<dyn ColorAt> {
+        Box::new(ConstColor::new(Color::new(value, value, value)))
+    }
+
+    fn material(roughness: f64, metallic: f64) -> Microfacet {
+        Microfacet::new(grey(1.0), grey(roughness), grey(metallic))
+    }
+
+    fn glass(roughness: f64) -> Microfacet {
+        material(roughness, 0.0).with_transmission(grey(1.0), 1.0 / 1.5)
+    }
+
+    fn point() -> ShadingPoint {
+        ShadingPoint {
+            texture: TextureContext::from_uv(Vector2d::new(0.0, 0.0)),
+            normal: Vector3d::z_axis(),
+            from_outside: true,
+        }
+    }
+
+    #[test]
+    fn test_sample_weight_matches_evaluate() {
+        let point = point();
+        let to_viewer = Vector3d::new(1.0, 0.0, 1.0).norm().unwrap();
+        let mut random = Random::new(11);
+        let materials = [material(0.3, 0.0), material(0.6, 1.0), material(1.0, 0.5), glass(0.4)];
+        for material in &materials {
+            for _ in 0..100 {
+                if let Some(sample) = material.sample(&point, &to_viewer, &mut random) {
+                    let f = material.evaluate(&point, &to_viewer, &sample.direction);
+                    let pdf = material.pdf(&point, &to_viewer, &sample.direction);
+                    let expected = f.scale(point.normal.dot(&sample.direction).abs() / pdf);
+                    assert!((expected.r - sample.weight.r).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_energy() {
+        // A white surface does not send back more light than it receives
+        let point = point();
+        let to_viewer = Vector3d::new(0.3, 0.0, 1.0).norm().unwrap();
+        let mut random = Random::new(5);
+        let count = 20000;
+        let materials = [material(0.1, 1.0), material(0.5, 1.0), material(0.5, 0.0), material(1.0, 0.0), glass(0.3)];
+        for material in &materials {
+            let albedo: f64 = (0..count)
+                .filter_map(|_| material.sample(&point, &to_viewer, &mut random))
+                // The radiance entering the glass is compressed by (1 / 1.5)^2, not the energy
+                .map(|sample| if sample.direction.z < 0.0 { sample.weight.r * 1.5 * 1.5 } else { sample.weight.r })
+                .sum::<f64>()
+                / count as f64;
+            assert!(albedo > 0.7 && albedo <= 1.0, "albedo {}", albedo);
+        }
+    }
+
+    #[test]
+    fn test_glossy_reflection() {
+        // Smooth metals reflect close to the mirror direction
+        let point = point();
+        let to_viewer = Vector3d::new(1.0, 0.0, 1.0).norm().unwrap();
+        let mirror = Vector3d::new(-1.0, 0.0, 1.0).norm().unwrap();
+        let mut random = Random::new(2);
+        let spread = |roughness: f64, random: &mut Random| {
+            let material = material(roughness, 1.0);
+            (0..200)
+                .filter_map(|_| material.sample_glossy(&point, &to_viewer, random))
+                .map(|sample| 1.0 - sample.direction.dot(&mirror))
+                .fold(0.0, f64::max)
+        };
+        assert!(spread(0.05, &mut random) < 0.01);
+        assert!(spread(0.6, &mut random) > 0.1);
+
+        let metal = Microfacet::new(Box::new(ConstColor::new(Color::new(1.0, 0.5, 0.2))), grey(0.05), grey(1.0));
+        let sample = metal.sample_glossy(&point, &to_viewer, &mut random).unwrap();
+        assert!(sample.weight.r > sample.weight.g && sample.weight.g > sample.weight.b);
+    }
+
+    #[test]
+    fn test_transmission() {
+        // A smooth glass refracts close to the Snell direction
+        let mut point = point();
+        let to_viewer = Vector3d::new(1.0, 0.0, 1.0).norm().unwrap();
+        let refracted = refraction_direction(&-to_viewer.clone(), &point.normal, 1.0 / 1.5).unwrap();
+        let mut random = Random::new(4);
+        let glass = glass(0.05);
+        let transmitted: Vec<BsdfSample> = (0..200)
+            .filter_map(|_| glass.sample_glossy(&point, &to_viewer, &mut random))
+            .filter(|sample| sample.direction.z < 0.0)
+            .collect();
+        assert!(transmitted.len() > 100);
+        for sample in &transmitted {
+            assert!(sample.direction.dot(&refracted) > 0.99);
+            assert!(sample.weight.r > 0.0 && sample.weight.r < 1.0);
+        }
+
+        // From the inside at a grazing angle everything is reflected
+        point.from_outside = false;
+        let grazing = Vector3d::new(1.0, 0.0, 0.2).norm().unwrap();
+        for _ in 0..200 {
+            if let Some(sample) = glass.sample(&point, &grazing, &mut random) {
+                assert!(sample.direction.z > 0.0);
+            }
+        }
+        // Opaque materials let nothing through
+        let below = Vector3d::new(-1.0, 0.0, -1.0).norm().unwrap();
+        assert_eq!(material(0.3, 0.0).evaluate(&point, &to_viewer, &below), BLACK);
+    }
+}
@@ -1,2 +1,16 @@
+mod material;
+pub use material::*;
 mod surface;
-pub use surface::*;
\ No newline at end of file
+pub use surface::*;
+mod image_texture;
+pub use image_texture::*;
+mod patterns;
+pub use patterns::*;
+mod noise;
+pub use noise::*;
+mod fresnel;
+pub use fresnel::*;
+mod highlight;
+pub use highlight::*;
+mod microfacet;
+pub use microfacet::*;
@@ -0,0 +1,578 @@
+use std::{f64::consts::PI, path::Path, sync::OnceLock};
+
+use crate::{
+    color::Color,
+    error::ResourceError,
+    sampling::Random,
+    vector::{Vector2d, Vector3d},
+};
+
+use super::{default_center, distance_to_axis, load_both, one, ColorAt, TextureContext, TextureSpace};
+
+// Noise functions and the textures blending two nested textures with them, flat in uv space
+// or solid in object and world space.
+// https://mrl.cs.nyu.edu/~perlin/noise/
+// https://weber.itn.liu.se/~stegu/simplexnoise/simplexnoise.pdf
+
+// Same permutation for every render, so that the textures do not change between frames.
+const PERMUTATION_SEED: u64 = 0x5eed;
+
+// Permutation of 0..256 repeated twice to avoid wrapping the indices.
+fn permutation() -> &'static [usize; 512] {
+    static PERMUTATION: OnceLock<[usize; 512]> = OnceLock::new();
+    PERMUTATION.get_or_init(|| {
+        let mut values: Vec<usize> = (0..256).collect();
+        let mut random = Random::new(PERMUTATION_SEED);
+        for i in (1..256).rev() {
+            let j = (random.next_u64() % (i as u64 + 1)) as usize;
+            values.swap(i, j);
+        }
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = values[i % 256];
+        }
+        permutation
+    })
+}
+
+// Gradients toward the middles of the edges of a cube
+const GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+fn gradient_dot(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let gradient = GRADIENTS[hash % 12];
+    gradient[0] * x + gradient[1] * y + gradient[2] * z
+}
+
+fn lattice(value: f64) -> (usize, f64) {
+    let floor = value.floor();
+    ((floor as i64).rem_euclid(256) as usize, value - floor)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// Improved Perlin noise, in [-1, 1].
+pub fn perlin_noise(position: &Vector3d) -> f64 {
+    let p = permutation();
+    let (xi, x) = lattice(position.x);
+    let (yi, y) = lattice(position.y);
+    let (zi, z) = lattice(position.z);
+    let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = p[xi] + yi;
+    let (aa, ab) = (p[a] + zi, p[a + 1] + zi);
+    let b = p[xi + 1] + yi;
+    let (ba, bb) = (p[b] + zi, p[b + 1] + zi);
+
+    let near = lerp(
+        v,
+        lerp(u, gradient_dot(p[aa], x, y, z), gradient_dot(p[ba], x - 1.0, y, z)),
+        lerp(u, gradient_dot(p[ab], x, y - 1.0, z), gradient_dot(p[bb], x - 1.0, y - 1.0, z)),
+    );
+    let far = lerp(
+        v,
+        lerp(u, gradient_dot(p[aa + 1], x, y, z - 1.0), gradient_dot(p[ba + 1], x - 1.0, y, z - 1.0)),
+        lerp(
+            u,
+            gradient_dot(p[ab + 1], x, y - 1.0, z - 1.0),
+            gradient_dot(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+        ),
+    );
+    lerp(w, near, far).clamp(-1.0, 1.0)
+}
+
+// Simplex noise, in [-1, 1]: cheaper than Perlin noise and without its axis aligned artifacts.
+pub fn simplex_noise(position: &Vector3d) -> f64 {
+    const SKEW: f64 = 1.0 / 3.0;
+    const UNSKEW: f64 = 1.0 / 6.0;
+    let p = permutation();
+
+    // Simplex cell containing the point and position from its first corner
+    let s = (position.x + position.y + position.z) * SKEW;
+    let (i, j, k) = ((position.x + s).floor(), (position.y + s).floor(), (position.z + s).floor());
+    let t = (i + j + k) * UNSKEW;
+    let first = [position.x - (i - t), position.y - (j - t), position.z - (k - t)];
+
+    // Order of the coordinates giving the corners crossed from the first to the last one
+    let [x0, y0, z0] = first;
+    let (second, third) = if x0 >= y0 {
+        if y0 >= z0 {
+            ([1, 0, 0], [1, 1, 0])
+        } else if x0 >= z0 {
+            ([1, 0, 0], [1, 0, 1])
+        } else {
+            ([0, 0, 1], [1, 0, 1])
+        }
+    } else if y0 < z0 {
+        ([0, 0, 1], [0, 1, 1])
+    } else if x0 < z0 {
+        ([0, 1, 0], [0, 1, 1])
+    } else {
+        ([0, 1, 0], [1, 1, 0])
+    };
+
+    let (ii, jj, kk) = (
+        (i as i64).rem_euclid(256) as usize,
+        (j as i64).rem_euclid(256) as usize,
+        (k as i64).rem_euclid(256) as usize,
+    );
+    let corners = [[0, 0, 0], second, third, [1, 1, 1]];
+    let total: f64 = corners
+        .iter()
+        .enumerate()
+        .map(|(n, corner)| {
+            let offset = n as f64 * UNSKEW;
+            let x = x0 - corner[0] as f64 + offset;
+            let y = y0 - corner[1] as f64 + offset;
+            let z = z0 - corner[2] as f64 + offset;
+            let falloff = 0.6 - x * x - y * y - z * z;
+            if falloff < 0.0 {
+                0.0
+            } else {
+                let hash = p[ii + corner[0] + p[jj + corner[1] + p[kk + corner[2]]]];
+                falloff.powi(4) * gradient_dot(hash, x, y, z)
+            }
+        })
+        .sum();
+    (32.0 * total).clamp(-1.0, 1.0)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    #[default]
+    Perlin,
+    Simplex,
+}
+
+impl NoiseKind {
+    pub fn noise(&self, position: &Vector3d) -> f64 {
+        match self {
+            NoiseKind::Perlin => perlin_noise(position),
+            NoiseKind::Simplex => simplex_noise(position),
+        }
+    }
+
+    // Fractal brownian motion: sum of `octaves` noises of increasing frequency and
+    // decreasing amplitude, in [-1, 1].
+    pub fn fbm(&self, position: &Vector3d, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        self.octaves(position, octaves, lacunarity, gain, |noise| noise)
+    }
+
+    // Same as fbm with the absolute value of the noises, in [0, 1].
+    pub fn turbulence(&self, position: &Vector3d, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        self.octaves(position, octaves, lacunarity, gain, f64::abs)
+    }
+
+    fn octaves<F>(&self, position: &Vector3d, octaves: u32, lacunarity: f64, gain: f64, shape: F) -> f64
+    where
+        F: Fn(f64) -> f64,
+    {
+        let (mut frequency, mut amplitude) = (1.0, 1.0);
+        let (mut total, mut max) = (0.0, 0.0);
+        for _ in 0..octaves.max(1) {
+            total += amplitude * shape(self.noise(&position.each_mul(frequency)));
+            max += amplitude;
+            frequency *= lacunarity;
+            amplitude *= gain;
+        }
+        total / max
+    }
+}
+
+fn default_octaves() -> u32 {
+    5
+}
+
+fn default_lacunarity() -> f64 {
+    2.0
+}
+
+fn default_gain() -> f64 {
+    0.5
+}
+
+fn default_veins() -> f64 {
+    4.0
+}
+
+fn default_marble_strength() -> f64 {
+    5.0
+}
+
+fn default_rings() -> f64 {
+    8.0
+}
+
+fn default_wood_strength() -> f64 {
+    0.05
+}
+
+// Position in the noise of the texture point, the noises vary over about 1 / `scale`.
+fn noise_position(context: &TextureContext, space: TextureSpace, scale: f64) -> Vector3d {
+    context.point(space).each_mul(scale)
+}
+
+// Blend of `low` and `high` driven by a single noise.
+#[derive(Serialize, Deserialize)]
+pub struct Noise {
+    low: Box<dyn ColorAt>,
+    high: Box<dyn ColorAt>,
+    #[serde(default)]
+    kind: NoiseKind,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default)]
+    space: TextureSpace,
+}
+
+impl Noise {
+    pub fn new(low: Box<dyn ColorAt>, high: Box<dyn ColorAt>, kind: NoiseKind, scale: f64) -> Self {
+        Self {
+            low,
+            high,
+            kind,
+            scale,
+            space: TextureSpace::default(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+}
+
+#[typetag::serde(name = "noise")]
+impl ColorAt for Noise {
+    fn color(&self, context: &TextureContext) -> Color {
+        let t = 0.5 + 0.5 * self.kind.noise(&noise_position(context, self.space, self.scale));
+        self.low.color(context).lerp(&self.high.color(context), t)
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.low, &mut self.high, scene_dir)
+    }
+}
+
+// Blend of `low` and `high` driven by a fractal noise (clouds...).
+#[derive(Serialize, Deserialize)]
+pub struct Fbm {
+    low: Box<dyn ColorAt>,
+    high: Box<dyn ColorAt>,
+    #[serde(default)]
+    kind: NoiseKind,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default)]
+    space: TextureSpace,
+    #[serde(default = "default_octaves")]
+    octaves: u32,
+    // Frequency ratio between two octaves
+    #[serde(default = "default_lacunarity")]
+    lacunarity: f64,
+    // Amplitude ratio between two octaves
+    #[serde(default = "default_gain")]
+    gain: f64,
+}
+
+impl Fbm {
+    pub fn new(low: Box<dyn ColorAt>, high: Box<dyn ColorAt>, kind: NoiseKind, scale: f64) -> Self {
+        Self {
+            low,
+            high,
+            kind,
+            scale,
+            space: TextureSpace::default(),
+            octaves: default_octaves(),
+            lacunarity: default_lacunarity(),
+            gain: default_gain(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+
+    pub fn with_octaves(self, octaves: u32, lacunarity: f64, gain: f64) -> Self {
+        Self {
+            octaves,
+            lacunarity,
+            gain,
+            ..self
+        }
+    }
+}
+
+#[typetag::serde(name = "fbm")]
+impl ColorAt for Fbm {
+    fn color(&self, context: &TextureContext) -> Color {
+        let position = noise_position(context, self.space, self.scale);
+        let t = 0.5 + 0.5 * self.kind.fbm(&position, self.octaves, self.lacunarity, self.gain);
+        self.low.color(context).lerp(&self.high.color(context), t)
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.low, &mut self.high, scene_dir)
+    }
+}
+
+// Blend of `low` and `high` driven by a turbulence, with sharp creases where the noises
+// change sign (flames...).
+#[derive(Serialize, Deserialize)]
+pub struct Turbulence {
+    low: Box<dyn ColorAt>,
+    high: Box<dyn ColorAt>,
+    #[serde(default)]
+    kind: NoiseKind,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default)]
+    space: TextureSpace,
+    #[serde(default = "default_octaves")]
+    octaves: u32,
+    #[serde(default = "default_lacunarity")]
+    lacunarity: f64,
+    #[serde(default = "default_gain")]
+    gain: f64,
+}
+
+impl Turbulence {
+    pub fn new(low: Box<dyn ColorAt>, high: Box<dyn ColorAt>, kind: NoiseKind, scale: f64) -> Self {
+        Self {
+            low,
+            high,
+            kind,
+            scale,
+            space: TextureSpace::default(),
+            octaves: default_octaves(),
+            lacunarity: default_lacunarity(),
+            gain: default_gain(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+}
+
+#[typetag::serde(name = "turbulence")]
+impl ColorAt for Turbulence {
+    fn color(&self, context: &TextureContext) -> Color {
+        let position = noise_position(context, self.space, self.scale);
+        let t = self.kind.turbulence(&position, self.octaves, self.lacunarity, self.gain);
+        self.low.color(context).lerp(&self.high.color(context), t)
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.low, &mut self.high, scene_dir)
+    }
+}
+
+// `veins` sine waves per unit along u (or x), bent by a turbulence of the given `strength`.
+#[derive(Serialize, Deserialize)]
+pub struct Marble {
+    base: Box<dyn ColorAt>,
+    vein: Box<dyn ColorAt>,
+    #[serde(default)]
+    kind: NoiseKind,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default)]
+    space: TextureSpace,
+    #[serde(default = "default_octaves")]
+    octaves: u32,
+    #[serde(default = "default_veins")]
+    veins: f64,
+    #[serde(default = "default_marble_strength")]
+    strength: f64,
+}
+
+impl Marble {
+    pub fn new(base: Box<dyn ColorAt>, vein: Box<dyn ColorAt>, kind: NoiseKind, scale: f64) -> Self {
+        Self {
+            base,
+            vein,
+            kind,
+            scale,
+            space: TextureSpace::default(),
+            octaves: default_octaves(),
+            veins: default_veins(),
+            strength: default_marble_strength(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+}
+
+#[typetag::serde(name = "marble")]
+impl ColorAt for Marble {
+    fn color(&self, context: &TextureContext) -> Color {
+        let position = noise_position(context, self.space, self.scale);
+        let turbulence = self.kind.turbulence(&position, self.octaves, default_lacunarity(), default_gain());
+        let along = context.point(self.space).x;
+        let wave = (2.0 * PI * self.veins * along + self.strength * turbulence).sin();
+        // Thin veins where the wave is close to 1
+        let t = (0.5 + 0.5 * wave).powi(4);
+        self.base.color(context).lerp(&self.vein.color(context), t)
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.base, &mut self.vein, scene_dir)
+    }
+}
+
+// Growth rings around the z axis moved to `center`, `rings` per unit of distance, made
+// irregular by a noise.
+#[derive(Serialize, Deserialize)]
+pub struct Wood {
+    light: Box<dyn ColorAt>,
+    dark: Box<dyn ColorAt>,
+    #[serde(default)]
+    kind: NoiseKind,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default)]
+    space: TextureSpace,
+    #[serde(default = "default_center")]
+    center: Vector2d,
+    #[serde(default = "default_rings")]
+    rings: f64,
+    #[serde(default = "default_wood_strength")]
+    strength: f64,
+}
+
+impl Wood {
+    pub fn new(light: Box<dyn ColorAt>, dark: Box<dyn ColorAt>, kind: NoiseKind, scale: f64) -> Self {
+        Self {
+            light,
+            dark,
+            kind,
+            scale,
+            space: TextureSpace::default(),
+            center: default_center(),
+            rings: default_rings(),
+            strength: default_wood_strength(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+}
+
+#[typetag::serde(name = "wood")]
+impl ColorAt for Wood {
+    fn color(&self, context: &TextureContext) -> Color {
+        let distance = distance_to_axis(&context.point(self.space), &self.center);
+        let noise = self.kind.noise(&noise_position(context, self.space, self.scale));
+        let ring = (distance + self.strength * noise) * self.rings;
+        self.light.color(context).lerp(&self.dark.color(context), ring - ring.floor())
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.light, &mut self.dark, scene_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::{BLACK, WHITE},
+        surfaces::ConstColor,
+    };
+
+    fn positions() -> impl Iterator<Item = Vector3d> {
+        let mut random = Random::new(3);
+        (0..2000).map(move |_| {
+            let mut coordinate = || random.next_f64() * 40.0 - 20.0;
+            Vector3d::new(coordinate(), coordinate(), coordinate())
+        })
+    }
+
+    #[test]
+    fn test_noise_range_and_continuity() {
+        for kind in &[NoiseKind::Perlin, NoiseKind::Simplex] {
+            let (mut min, mut max) = (0.0f64, 0.0f64);
+            for position in positions() {
+                let value = kind.noise(&position);
+                min = min.min(value);
+                max = max.max(value);
+                let close = kind.noise(&position.each_add(1e-4));
+                assert!((value - close).abs() < 1e-2, "{:?} jumps at {}", kind, position);
+            }
+            // Covers a good part of [-1, 1]
+            assert!(min < -0.5 && max > 0.5, "{:?} in [{}, {}]", kind, min, max);
+        }
+        // Zero on the lattice points
+        assert_eq!(perlin_noise(&Vector3d::new(3.0, -2.0, 7.0)), 0.0);
+    }
+
+    #[test]
+    fn test_fbm_and_turbulence_range() {
+        for kind in &[NoiseKind::Perlin, NoiseKind::Simplex] {
+            for position in positions() {
+                let fbm = kind.fbm(&position, 6, 2.0, 0.5);
+                assert!((-1.0..=1.0).contains(&fbm));
+                let turbulence = kind.turbulence(&position, 6, 2.0, 0.5);
+                assert!((0.0..=1.0).contains(&turbulence));
+            }
+        }
+    }
+
+    #[test]
+    fn test_textures_blend_inputs() {
+        let black = || -> Box<dyn ColorAt> { Box::new(ConstColor::new(BLACK)) };
+        let white = || -> Box<dyn ColorAt> { Box::new(ConstColor::new(WHITE)) };
+        let textures: Vec<Box<dyn ColorAt>> = vec![
+            Box::new(Noise::new(black(), white(), NoiseKind::Simplex, 8.0)),
+            Box::new(Fbm::new(black(), white(), NoiseKind::Perlin, 8.0).with_octaves(3, 2.0, 0.5)),
+            Box::new(Turbulence::new(black(), white(), NoiseKind::Perlin, 8.0)),
+            Box::new(Marble::new(black(), white(), NoiseKind::Perlin, 4.0)),
+            Box::new(Wood::new(black(), white(), NoiseKind::Simplex, 4.0)),
+        ];
+        for texture in &textures {
+            let greys: Vec<f64> = (0..100)
+                .map(|i| Vector2d::new(i as f64 * 0.0123, 1.0 - i as f64 * 0.0071))
+                .map(|uv| texture.color(&TextureContext::from_uv(uv)).r)
+                .collect();
+            assert!(greys.iter().all(|grey| (0.0..=1.0).contains(grey)));
+            // Not a plain color
+            assert!(greys.iter().any(|grey| (grey - greys[0]).abs() > 0.1));
+        }
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let json = r#"{
+            "type": "marble",
+            "kind": "simplex",
+            "base": { "type": "const_color", "color": { "r": 0.9, "g": 0.9, "b": 0.9 } },
+            "vein": {
+                "type": "fbm",
+                "low": { "type": "const_color", "color": { "r": 0.1, "g": 0.1, "b": 0.1 } },
+                "high": { "type": "const_color", "color": { "r": 0.3, "g": 0.3, "b": 0.3 } }
+            }
+        }"#;
+        let texture: Box<dyn ColorAt> = serde_json::from_str(json).unwrap();
+        let grey = texture.color(&TextureContext::from_uv(Vector2d::new(0.3, 0.6))).r;
+        assert!((0.1..=0.9).contains(&grey));
+    }
+}
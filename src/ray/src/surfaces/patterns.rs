@@ -0,0 +1,340 @@
+use std::path::Path;
+
+use crate::{
+    color::Color,
+    error::ResourceError,
+    vector::{Vector2d, Vector3d},
+};
+
+use super::{ColorAt, TextureContext, TextureSpace};
+
+// Geometric patterns alternating or blending two nested textures read at the same point.
+// The checker, stripes and rings are solid textures when evaluated in object or world space.
+
+pub(crate) fn one() -> f64 {
+    1.0
+}
+
+pub(crate) fn default_center() -> Vector2d {
+    Vector2d::new(0.5, 0.5)
+}
+
+fn default_radius() -> f64 {
+    0.5
+}
+
+fn distance(a: &Vector2d, b: &Vector2d) -> f64 {
+    let d = a - b;
+    d.dot(&d).sqrt()
+}
+
+// Distance to the axis parallel to z going through `center`.
+pub(crate) fn distance_to_axis(point: &Vector3d, center: &Vector2d) -> f64 {
+    distance(&Vector2d::new(point.x, point.y), center)
+}
+
+pub(crate) fn load_both(
+    a: &mut Box<dyn ColorAt>,
+    b: &mut Box<dyn ColorAt>,
+    scene_dir: &Path,
+) -> Result<(), ResourceError> {
+    a.load_resources(scene_dir)?;
+    b.load_resources(scene_dir)
+}
+
+// `scale` squares (or cubes) per unit.
+#[derive(Serialize, Deserialize)]
+pub struct Checker {
+    even: Box<dyn ColorAt>,
+    odd: Box<dyn ColorAt>,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default)]
+    space: TextureSpace,
+}
+
+impl Checker {
+    pub fn new(even: Box<dyn ColorAt>, odd: Box<dyn ColorAt>, scale: f64) -> Self {
+        Self {
+            even,
+            odd,
+            scale,
+            space: TextureSpace::default(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+}
+
+#[typetag::serde(name = "checker")]
+impl ColorAt for Checker {
+    fn color(&self, context: &TextureContext) -> Color {
+        let point = context.point(self.space).each_mul(self.scale);
+        let cell = point.x.floor() + point.y.floor() + point.z.floor();
+        if cell.rem_euclid(2.0) < 1.0 {
+            self.even.color(context)
+        } else {
+            self.odd.color(context)
+        }
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.even, &mut self.odd, scene_dir)
+    }
+}
+
+// `scale` pairs of stripes per unit along the direction making `angle` degrees with the
+// u (or x) axis, in the uv (or xy) plane.
+#[derive(Serialize, Deserialize)]
+pub struct Stripes {
+    even: Box<dyn ColorAt>,
+    odd: Box<dyn ColorAt>,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default)]
+    angle: f64,
+    #[serde(default)]
+    space: TextureSpace,
+}
+
+impl Stripes {
+    pub fn new(even: Box<dyn ColorAt>, odd: Box<dyn ColorAt>, scale: f64, angle: f64) -> Self {
+        Self {
+            even,
+            odd,
+            scale,
+            angle,
+            space: TextureSpace::default(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+}
+
+#[typetag::serde(name = "stripes")]
+impl ColorAt for Stripes {
+    fn color(&self, context: &TextureContext) -> Color {
+        let point = context.point(self.space);
+        let angle = self.angle.to_radians();
+        let along = point.x * angle.cos() + point.y * angle.sin();
+        if (along * self.scale * 2.0).floor().rem_euclid(2.0) < 1.0 {
+            self.even.color(context)
+        } else {
+            self.odd.color(context)
+        }
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.even, &mut self.odd, scene_dir)
+    }
+}
+
+// Concentric rings around `center`, `scale` pairs of rings per unit of distance. In 3D the
+// rings are cylinders around the z axis moved to `center`.
+#[derive(Serialize, Deserialize)]
+pub struct Rings {
+    even: Box<dyn ColorAt>,
+    odd: Box<dyn ColorAt>,
+    #[serde(default = "one")]
+    scale: f64,
+    #[serde(default = "default_center")]
+    center: Vector2d,
+    #[serde(default)]
+    space: TextureSpace,
+}
+
+impl Rings {
+    pub fn new(even: Box<dyn ColorAt>, odd: Box<dyn ColorAt>, scale: f64, center: Vector2d) -> Self {
+        Self {
+            even,
+            odd,
+            scale,
+            center,
+            space: TextureSpace::default(),
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        Self { space, ..self }
+    }
+}
+
+#[typetag::serde(name = "rings")]
+impl ColorAt for Rings {
+    fn color(&self, context: &TextureContext) -> Color {
+        let distance = distance_to_axis(&context.point(self.space), &self.center);
+        if (distance * self.scale * 2.0).floor().rem_euclid(2.0) < 1.0 {
+            self.even.color(context)
+        } else {
+            self.odd.color(context)
+        }
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.even, &mut self.odd, scene_dir)
+    }
+}
+
+// Blend from `from` at the u = 0 edge to `to` at the u = 1 edge, turned by `angle` degrees
+// around the center of the uv square.
+#[derive(Serialize, Deserialize)]
+pub struct LinearGradient {
+    from: Box<dyn ColorAt>,
+    to: Box<dyn ColorAt>,
+    #[serde(default)]
+    angle: f64,
+}
+
+impl LinearGradient {
+    pub fn new(from: Box<dyn ColorAt>, to: Box<dyn ColorAt>, angle: f64) -> Self {
+        Self { from, to, angle }
+    }
+}
+
+#[typetag::serde(name = "linear_gradient")]
+impl ColorAt for LinearGradient {
+    fn color(&self, context: &TextureContext) -> Color {
+        let angle = self.angle.to_radians();
+        let centered = &context.uv - &default_center();
+        let t = 0.5 + centered.x * angle.cos() + centered.y * angle.sin();
+        self.from.color(context).lerp(&self.to.color(context), t.clamp(0.0, 1.0))
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.from, &mut self.to, scene_dir)
+    }
+}
+
+// Blend from `inner` at the uv `center` to `outer` at `radius` and beyond.
+#[derive(Serialize, Deserialize)]
+pub struct RadialGradient {
+    inner: Box<dyn ColorAt>,
+    outer: Box<dyn ColorAt>,
+    #[serde(default = "default_center")]
+    center: Vector2d,
+    #[serde(default = "default_radius")]
+    radius: f64,
+}
+
+impl RadialGradient {
+    pub fn new(inner: Box<dyn ColorAt>, outer: Box<dyn ColorAt>, center: Vector2d, radius: f64) -> Self {
+        Self {
+            inner,
+            outer,
+            center,
+            radius,
+        }
+    }
+}
+
+#[typetag::serde(name = "radial_gradient")]
+impl ColorAt for RadialGradient {
+    fn color(&self, context: &TextureContext) -> Color {
+        let t = distance(&context.uv, &self.center) / self.radius;
+        self.inner.color(context).lerp(&self.outer.color(context), t.clamp(0.0, 1.0))
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        load_both(&mut self.inner, &mut self.outer, scene_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::{BLACK, WHITE},
+        surfaces::ConstColor,
+    };
+
+    fn black() -> Box<dyn ColorAt> {
+        Box::new(ConstColor::new(BLACK))
+    }
+
+    fn white() -> Box<dyn ColorAt> {
+        Box::new(ConstColor::new(WHITE))
+    }
+
+    fn grey_at(texture: &dyn ColorAt, u: f64, v: f64) -> f64 {
+        texture.color(&TextureContext::from_uv(Vector2d::new(u, v))).r
+    }
+
+    #[test]
+    fn test_checker() {
+        let checker = Checker::new(black(), white(), 2.0);
+        assert_eq!(grey_at(&checker, 0.1, 0.1), 0.0);
+        assert_eq!(grey_at(&checker, 0.6, 0.1), 1.0);
+        assert_eq!(grey_at(&checker, 0.6, 0.6), 0.0);
+        assert_eq!(grey_at(&checker, -0.1, 0.1), 1.0);
+    }
+
+    #[test]
+    fn test_stripes() {
+        let stripes = Stripes::new(black(), white(), 1.0, 0.0);
+        assert_eq!(grey_at(&stripes, 0.2, 0.9), 0.0);
+        assert_eq!(grey_at(&stripes, 0.7, 0.1), 1.0);
+        let turned = Stripes::new(black(), white(), 1.0, 90.0);
+        assert_eq!(grey_at(&turned, 0.7, 0.2), 0.0);
+        assert_eq!(grey_at(&turned, 0.2, 0.7), 1.0);
+    }
+
+    #[test]
+    fn test_rings() {
+        let rings = Rings::new(black(), white(), 2.0, default_center());
+        assert_eq!(grey_at(&rings, 0.5, 0.5), 0.0);
+        assert_eq!(grey_at(&rings, 0.8, 0.5), 1.0);
+        assert_eq!(grey_at(&rings, 0.5, 1.1), 0.0);
+    }
+
+    #[test]
+    fn test_solid_checker() {
+        let checker = Checker::new(black(), white(), 1.0).with_space(TextureSpace::World);
+        let mut context = TextureContext::from_uv(Vector2d::new(0.1, 0.1));
+        context.position = Vector3d::new(0.5, 0.5, 1.5);
+        assert_eq!(checker.color(&context).r, 1.0);
+        context.position = Vector3d::new(0.5, 1.5, 1.5);
+        assert_eq!(checker.color(&context).r, 0.0);
+        // The object position is ignored in world space
+        context.object_position = Vector3d::new(0.5, 0.5, 0.5);
+        assert_eq!(checker.color(&context).r, 0.0);
+        assert_eq!(checker.with_space(TextureSpace::Object).color(&context).r, 0.0);
+    }
+
+    #[test]
+    fn test_gradients() {
+        let linear = LinearGradient::new(black(), white(), 0.0);
+        assert_eq!(grey_at(&linear, 0.0, 0.3), 0.0);
+        assert!((grey_at(&linear, 0.25, 0.7) - 0.25).abs() < 1e-9);
+        assert_eq!(grey_at(&linear, 1.5, 0.3), 1.0);
+        let vertical = LinearGradient::new(black(), white(), 90.0);
+        assert!((grey_at(&vertical, 0.1, 0.75) - 0.75).abs() < 1e-9);
+
+        let radial = RadialGradient::new(white(), black(), default_center(), 0.5);
+        assert_eq!(grey_at(&radial, 0.5, 0.5), 1.0);
+        assert!((grey_at(&radial, 0.75, 0.5) - 0.5).abs() < 1e-9);
+        assert_eq!(grey_at(&radial, 0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_nested() {
+        let json = r#"{
+            "type": "checker",
+            "scale": 2.0,
+            "even": { "type": "const_color", "color": { "r": 0.0, "g": 0.0, "b": 0.0 } },
+            "odd": {
+                "type": "stripes",
+                "angle": 90.0,
+                "even": { "type": "const_color", "color": { "r": 1.0, "g": 0.0, "b": 0.0 } },
+                "odd": { "type": "const_color", "color": { "r": 0.0, "g": 0.0, "b": 1.0 } }
+            }
+        }"#;
+        let texture: Box<dyn ColorAt> = serde_json::from_str(json).unwrap();
+        assert_eq!(texture.color(&TextureContext::from_uv(Vector2d::new(0.1, 0.1))), BLACK);
+        assert_eq!(texture.color(&TextureContext::from_uv(Vector2d::new(0.6, 0.1))), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.color(&TextureContext::from_uv(Vector2d::new(0.1, 0.6))), Color::new(0.0, 0.0, 1.0));
+    }
+}
@@ -1,8 +1,65 @@
-use crate::{color::Color, vector::Vector2d};
+use std::{f64::consts::PI, path::Path};
+
+use crate::{
+    color::{Color, BLACK},
+    error::ResourceError,
+    sampling::{cosine_hemisphere, Random},
+    vector::{Vector2d, Vector3d},
+};
+
+use super::{refraction_direction, specular_direction, BsdfSample, Fresnel, Highlight, Material, ShadingPoint};
+
+// Where a texture is read: the hit point in world space and in the space of the thing before
+// its transforms, the outward normal and the uv coordinates of the thing.
+#[derive(Clone, Debug)]
+pub struct TextureContext {
+    pub uv: Vector2d,
+    pub position: Vector3d,
+    pub object_position: Vector3d,
+    pub normal: Vector3d,
+}
+
+impl TextureContext {
+    // Context of a flat texture, the positions being (u, v, 0).
+    pub fn from_uv(uv: Vector2d) -> Self {
+        let position = Vector3d::new(uv.x, uv.y, 0.0);
+        Self {
+            uv,
+            object_position: position.clone(),
+            position,
+            normal: Vector3d::z_axis(),
+        }
+    }
+
+    // Position of the context in the given space, the uv coordinates giving (u, v, 0).
+    pub fn point(&self, space: TextureSpace) -> Vector3d {
+        match space {
+            TextureSpace::Uv => Vector3d::new(self.uv.x, self.uv.y, 0.0),
+            TextureSpace::Object => self.object_position.clone(),
+            TextureSpace::World => self.position.clone(),
+        }
+    }
+}
+
+// Coordinates read by the textures that can be evaluated in 3D (solid textures): those in
+// object space are carved in the thing and follow its transforms.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureSpace {
+    #[default]
+    Uv,
+    Object,
+    World,
+}
 
 #[typetag::serde(tag = "type")]
-pub trait ColorAt {
-    fn color(&self, uv: &Vector2d) -> Color;
+pub trait ColorAt: Send + Sync {
+    fn color(&self, context: &TextureContext) -> Color;
+
+    // Loads external files (images...), paths are relative to the scene file directory.
+    fn load_resources(&mut self, _scene_dir: &Path) -> Result<(), ResourceError> {
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -12,7 +69,7 @@ pub struct ConstColor {
 
 #[typetag::serde(name = "const_color")]
 impl ColorAt for ConstColor {
-    fn color(&self, _uv: &Vector2d) -> Color {
+    fn color(&self, _context: &TextureContext) -> Color {
         self.color.clone()
     }
 }
@@ -30,7 +87,34 @@ pub struct Surface {
     diffuse: Box<dyn ColorAt>,
     specular: Box<dyn ColorAt>,
     refraction: Box<dyn ColorAt>,
+    // Index outside over index inside (n1 / n2 entering the thing), 1 / 1.5 for a glass in the air
     refraction_ratio: f64,
+    // Dielectric mode: the light going through the refraction channel is split
+    // between reflection and transmission with the Fresnel coefficients.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fresnel: Option<Fresnel>,
+    // Specular highlights from the lights, tinted by the specular color, none when 0
+    #[serde(default, skip_serializing_if = "is_zero")]
+    shininess: f64,
+    #[serde(default)]
+    highlight: Highlight,
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+// Plain grey diffuse surface, used when a model does not provide its own material.
+impl Default for Surface {
+    fn default() -> Self {
+        Self::new(
+            Box::new(ConstColor::new(Color::new(0.0, 0.0, 0.0))),
+            Box::new(ConstColor::new(Color::new(0.8, 0.8, 0.8))),
+            Box::new(ConstColor::new(Color::new(0.0, 0.0, 0.0))),
+            Box::new(ConstColor::new(Color::new(0.0, 0.0, 0.0))),
+            1.0,
+        )
+    }
 }
 
 impl Surface {
@@ -47,26 +131,236 @@ impl Surface {
             specular,
             refraction,
             refraction_ratio,
+            fresnel: None,
+            shininess: 0.0,
+            highlight: Highlight::default(),
         }
     }
 
-    pub fn ambiant(&self, uv: &Vector2d) -> Color {
-        self.ambiant.color(uv)
+    pub fn with_fresnel(mut self, fresnel: Fresnel) -> Self {
+        self.fresnel = Some(fresnel);
+        self
+    }
+
+    pub fn with_shininess(mut self, shininess: f64, highlight: Highlight) -> Self {
+        self.shininess = shininess;
+        self.highlight = highlight;
+        self
     }
 
-    pub fn diffuse(&self, uv: &Vector2d) -> Color {
-        self.diffuse.color(uv)
+    pub fn ambiant(&self, context: &TextureContext) -> Color {
+        self.ambiant.color(context)
     }
 
-    pub fn specular(&self,uv: &Vector2d) -> Color {
-        self.specular.color(uv)
+    pub fn diffuse(&self, context: &TextureContext) -> Color {
+        self.diffuse.color(context)
     }
 
-    pub fn refraction(&self, uv: &Vector2d) -> Color {
-        self.refraction.color(uv)
+    pub fn specular(&self, context: &TextureContext) -> Color {
+        self.specular.color(context)
+    }
+
+    pub fn refraction(&self, context: &TextureContext) -> Color {
+        self.refraction.color(context)
     }
     pub fn refraction_ratio(&self) -> f64 {
         self.refraction_ratio
     }
-    
+
+    pub fn fresnel(&self) -> Option<Fresnel> {
+        self.fresnel
+    }
+
+    pub fn shininess(&self) -> f64 {
+        self.shininess
+    }
+
+    pub fn highlight(&self) -> Highlight {
+        self.highlight
+    }
+
+    fn channel_strengths(channels: &[Color; 3]) -> [f64; 3] {
+        let strength = |color: &Color| color.average().max(0.0);
+        [strength(&channels[0]), strength(&channels[1]), strength(&channels[2])]
+    }
+
+    // Highlight coefficient for the light coming from `to_light`, 0 without shininess.
+    fn highlight_coef(&self, point: &ShadingPoint, to_viewer: &Vector3d, to_light: &Vector3d) -> f64 {
+        if self.shininess > 0.0 {
+            self.highlight.intensity(&point.normal, to_light, to_viewer, self.shininess)
+        } else {
+            0.0
+        }
+    }
+
+    // Part of the light reflected by the interface and direction of the transmitted light:
+    // nothing is reflected in the legacy mode, the Fresnel reflectance in the dielectric
+    // mode and everything on total internal reflection.
+    fn split_at_interface(&self, point: &ShadingPoint, ray_dir: &Vector3d) -> (f64, Option<Vector3d>) {
+        // n1 / n2, the normal always faces the incoming ray
+        let eta = if point.from_outside {
+            self.refraction_ratio
+        } else {
+            1.0 / self.refraction_ratio
+        };
+        let cos_incident = -point.normal.dot(ray_dir);
+
+        let transmitted_dir = refraction_direction(ray_dir, &point.normal, eta);
+        let reflectance = match (&transmitted_dir, self.fresnel) {
+            (None, _) => 1.0,
+            (Some(_), None) => 0.0,
+            (Some(_), Some(fresnel)) => fresnel.reflectance(cos_incident, eta),
+        };
+        (reflectance, transmitted_dir)
+    }
+}
+
+// Lambertian diffuse channel with optional Phong highlights, perfect mirror and refraction
+// channels, all weighted by their own colors.
+#[typetag::serde(name = "legacy_phong")]
+impl Material for Surface {
+    fn evaluate(&self, point: &ShadingPoint, to_viewer: &Vector3d, to_light: &Vector3d) -> Color {
+        let cos = point.normal.dot(to_light);
+        if cos <= 0.0 {
+            return BLACK;
+        }
+        let diffuse = self.diffuse(&point.texture).scale(1.0 / PI);
+        let highlight = self.highlight_coef(point, to_viewer, to_light);
+        if highlight > 0.0 {
+            diffuse + self.specular(&point.texture).scale(highlight / (PI * cos))
+        } else {
+            diffuse
+        }
+    }
+
+    fn pdf(&self, point: &ShadingPoint, _to_viewer: &Vector3d, to_light: &Vector3d) -> f64 {
+        let cos = point.normal.dot(to_light);
+        let channels = [self.diffuse(&point.texture), self.specular(&point.texture), self.refraction(&point.texture)];
+        let strengths = Self::channel_strengths(&channels);
+        let total: f64 = strengths.iter().sum();
+        if cos <= 0.0 || total <= 0.0 {
+            return 0.0;
+        }
+        strengths[0] / total * cos / PI
+    }
+
+    // One of the diffuse, specular and refraction channels picked at random according to
+    // their strength.
+    fn sample(&self, point: &ShadingPoint, to_viewer: &Vector3d, random: &mut Random) -> Option<BsdfSample> {
+        let channels = [self.diffuse(&point.texture), self.specular(&point.texture), self.refraction(&point.texture)];
+        let strengths = Self::channel_strengths(&channels);
+        let total: f64 = strengths.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut choice = random.next_f64() * total;
+        let channel = (0..2)
+            .find(|channel| {
+                choice -= strengths[*channel];
+                choice < 0.0
+            })
+            .unwrap_or(2);
+
+        let ray_dir = -to_viewer.clone();
+        let direction = match channel {
+            0 => cosine_hemisphere(&point.normal, random),
+            1 => specular_direction(&ray_dir, &point.normal),
+            _ => {
+                let (reflectance, transmitted_dir) = self.split_at_interface(point, &ray_dir);
+                match transmitted_dir {
+                    Some(dir) if random.next_f64() >= reflectance => dir,
+                    _ => specular_direction(&ray_dir, &point.normal),
+                }
+            }
+        };
+        Some(BsdfSample {
+            direction,
+            // Channel color divided by the probability to pick it
+            weight: channels[channel].scale(total / strengths[channel]),
+            specular: channel != 0,
+        })
+    }
+
+    fn ambiant(&self, point: &ShadingPoint) -> Color {
+        self.ambiant(&point.texture)
+    }
+
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.ambiant.load_resources(scene_dir)?;
+        self.diffuse.load_resources(scene_dir)?;
+        self.specular.load_resources(scene_dir)?;
+        self.refraction.load_resources(scene_dir)
+    }
+
+    fn albedo(&self, point: &ShadingPoint) -> Color {
+        self.diffuse(&point.texture)
+    }
+
+    // Mirror reflection weighted by the specular color, and the light of the refraction channel
+    // going through the surface, except for the part reflected by the interface.
+    fn specular_rays(&self, point: &ShadingPoint, to_viewer: &Vector3d) -> Vec<BsdfSample> {
+        let ray_dir = -to_viewer.clone();
+        let mut rays = Vec::new();
+        let specular = self.specular(&point.texture);
+        if !specular.is_black() {
+            rays.push(BsdfSample {
+                direction: specular_direction(&ray_dir, &point.normal),
+                weight: specular,
+                specular: true,
+            });
+        }
+
+        let refraction = self.refraction(&point.texture);
+        if refraction.is_black() {
+            return rays;
+        }
+        let (reflectance, transmitted_dir) = self.split_at_interface(point, &ray_dir);
+        if reflectance > 0.0 {
+            rays.push(BsdfSample {
+                direction: specular_direction(&ray_dir, &point.normal),
+                weight: refraction.scale(reflectance),
+                specular: true,
+            });
+        }
+        match transmitted_dir {
+            Some(direction) if reflectance < 1.0 => rays.push(BsdfSample {
+                direction,
+                weight: refraction.scale(1.0 - reflectance),
+                specular: true,
+            }),
+            _ => {}
+        }
+        rays
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::WHITE, test_worlds::const_surface};
+
+    #[test]
+    fn test_refraction_ratio() {
+        // Glass in the air
+        let glass = const_surface(BLACK, BLACK, BLACK, WHITE, 1.0 / 1.5);
+        let mut point = ShadingPoint {
+            texture: TextureContext::from_uv(Vector2d::new(0.0, 0.0)),
+            normal: Vector3d::z_axis(),
+            from_outside: true,
+        };
+        let to_viewer = Vector3d::new(1.0, 0.0, 1.0).norm().unwrap();
+
+        // Entering the glass the light gets closer to the normal
+        let rays = glass.specular_rays(&point, &to_viewer);
+        assert_eq!(rays.len(), 1);
+        assert!((rays[0].direction.x + 0.5f64.sqrt() / 1.5).abs() < 1e-12);
+        assert!(rays[0].direction.z < 0.0);
+
+        // Leaving it at 45 degrees it is totally reflected
+        point.from_outside = false;
+        let rays = glass.specular_rays(&point, &to_viewer);
+        assert_eq!(rays.len(), 1);
+        assert!((rays[0].direction.x + 0.5f64.sqrt()).abs() < 1e-12);
+        assert!(rays[0].direction.z > 0.0);
+    }
 }
@@ -0,0 +1,82 @@
+// Small worlds shared by the rendering tests.
+use crate::{
+    cameras::PerspectiveCamera,
+    color::{Color, BLACK, WHITE},
+    lights::PointLight,
+    surfaces::{ConstColor, Fresnel, Material, Surface},
+    things::{Plane, Sphere},
+    vector::Vector3d,
+    world::World,
+};
+
+pub fn const_surface(ambiant: Color, diffuse: Color, specular: Color, refraction: Color, refraction_ratio: f64) -> Surface {
+    Surface::new(
+        Box::new(ConstColor::new(ambiant)),
+        Box::new(ConstColor::new(diffuse)),
+        Box::new(ConstColor::new(specular)),
+        Box::new(ConstColor::new(refraction)),
+        refraction_ratio,
+    )
+}
+
+pub fn diffuse_surface(diffuse: Color) -> Box<dyn Material> {
+    Box::new(const_surface(BLACK, diffuse, BLACK, BLACK, 1.0))
+}
+
+// JSON of a grey diffuse surface, for the things deserialized by the tests
+pub fn surface_json(grey: f64) -> String {
+    format!(
+        r#"{{
+            "ambiant": {{ "type": "const_color", "color": {{ "r": 0.0, "g": 0.0, "b": 0.0 }} }},
+            "diffuse": {{ "type": "const_color", "color": {{ "r": {0}, "g": {0}, "b": {0} }} }},
+            "specular": {{ "type": "const_color", "color": {{ "r": 0.0, "g": 0.0, "b": 0.0 }} }},
+            "refraction": {{ "type": "const_color", "color": {{ "r": 0.0, "g": 0.0, "b": 0.0 }} }},
+            "refraction_ratio": 1.0
+        }}"#,
+        grey
+    )
+}
+
+// Shiny sphere in front of the camera lit by one light
+pub fn test_world() -> World {
+    let surface = const_surface(
+        Color::new(0.1, 0.0, 0.0),
+        Color::new(0.8, 0.2, 0.8),
+        Color::new(0.5, 0.5, 0.5),
+        BLACK,
+        1.0,
+    );
+    let sphere = Sphere::new(Vector3d::new(5.0, 0.0, 0.0), 1.0, Box::new(surface));
+    let light = PointLight::new(Vector3d::new(0.0, 2.0, 2.0), WHITE);
+    World::new(Box::new(PerspectiveCamera::default()), vec![Box::new(sphere)], vec![Box::new(light)], WHITE, 2)
+}
+
+// Test world with a glass sphere between the camera and the shiny sphere
+pub fn glass_world(fresnel: Option<Fresnel>) -> World {
+    let surface = const_surface(BLACK, BLACK, BLACK, WHITE, 1.0 / 1.5);
+    let surface = match fresnel {
+        Some(fresnel) => surface.with_fresnel(fresnel),
+        None => surface,
+    };
+    let glass = Sphere::new(Vector3d::new(3.0, 0.0, 0.0), 0.5, Box::new(surface));
+    let mut world = test_world();
+    world.add_thing(Box::new(glass));
+    world
+}
+
+// Diffuse sphere on a diffuse floor
+pub fn diffuse_world() -> World {
+    let sphere = Sphere::new(Vector3d::new(5.0, 0.0, 0.0), 1.0, diffuse_surface(Color::new(0.8, 0.2, 0.2)));
+    let floor = Plane::new(
+        Vector3d::new(0.0, 0.0, -1.0),
+        Vector3d::z_axis(),
+        diffuse_surface(Color::new(0.7, 0.7, 0.7)),
+    )
+    .unwrap();
+    let light = PointLight::new(Vector3d::new(0.0, 2.0, 2.0), WHITE);
+    World::new(Box::new(PerspectiveCamera::default()), vec![Box::new(sphere), Box::new(floor)], vec![Box::new(light)], WHITE, 4)
+}
+
+pub fn is_finite(color: &Color) -> bool {
+    color.r.is_finite() && color.g.is_finite() && color.b.is_finite()
+}
@@ -0,0 +1,153 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use crate::{
+    error::ResourceError,
+    ray::Ray,
+    surfaces::Material,
+    vector::{BoundingBox, Vector2d, Vector3d},
+};
+
+use super::{Mesh, Thing, ThingHit, TransformStack};
+
+// A reference to one of the named geometries of the world, placed by its own transform stack.
+// All the instances of a geometry share the same data, nothing is hit until it is linked.
+#[derive(Serialize, Deserialize)]
+pub struct Instance {
+    geometry: String,
+    #[serde(default)]
+    transform: TransformStack,
+    // Replaces the surfaces of the geometry when given
+    #[serde(default)]
+    surface: Option<Box<dyn Material>>,
+    #[serde(skip, default = "unlinked")]
+    shared: Arc<dyn Thing>,
+}
+
+fn unlinked() -> Arc<dyn Thing> {
+    Arc::new(Mesh::default())
+}
+
+impl Instance {
+    pub fn new(geometry: String, transform: TransformStack, surface: Option<Box<dyn Material>>) -> Self {
+        Self {
+            geometry,
+            transform,
+            surface,
+            shared: unlinked(),
+        }
+    }
+}
+
+#[typetag::serde(name = "instance")]
+impl Thing for Instance {
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        // The shared geometry resources are loaded by the world
+        match self.surface.as_mut() {
+            Some(surface) => surface.load_resources(scene_dir),
+            None => Ok(()),
+        }
+    }
+
+    fn link_geometries(&mut self, geometries: &HashMap<String, Arc<dyn Thing>>) -> Result<(), ResourceError> {
+        let geometry = geometries
+            .get(&self.geometry)
+            .ok_or_else(|| ResourceError::UnknownGeometry(self.geometry.clone()))?;
+        self.shared = Arc::clone(geometry);
+        Ok(())
+    }
+
+    fn normal(&self, hit: &ThingHit) -> Vector3d {
+        let normal = self.shared.normal(&self.transform.hit_to_object(hit));
+        self.transform.normal_to_world(&normal)
+    }
+
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit> {
+        self.shared
+            .intersect(&self.transform.ray_to_object(ray))
+            .iter()
+            .map(|hit| self.transform.hit_to_world(hit))
+            .collect()
+    }
+
+    fn surface(&self) -> &dyn Material {
+        match &self.surface {
+            Some(surface) => surface.as_ref(),
+            None => self.shared.surface(),
+        }
+    }
+
+    fn surface_at(&self, hit: &ThingHit) -> &dyn Material {
+        match &self.surface {
+            Some(surface) => surface.as_ref(),
+            None => self.shared.surface_at(&self.transform.hit_to_object(hit)),
+        }
+    }
+
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d {
+        self.shared.get_uv_mapping(&self.transform.hit_to_object(hit))
+    }
+
+    fn object_position(&self, hit: &ThingHit) -> Vector3d {
+        self.shared.object_position(&self.transform.hit_to_object(hit))
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.shared
+            .bounding_box()
+            .map(|bounding_box| self.transform.bounding_box_to_world(&bounding_box))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cameras::PerspectiveCamera,
+        color::WHITE,
+        surfaces::Surface,
+        things::{Sphere, TransformStep},
+        world::World,
+    };
+
+    #[test]
+    fn test_instances_share_geometry() {
+        let sphere: Arc<dyn Thing> = Arc::new(Sphere::new(Vector3d::zero(), 1.0, Box::new(Surface::default())));
+        let mut geometries = HashMap::new();
+        geometries.insert("ball".to_string(), sphere);
+
+        let instances: Vec<Instance> = [-2.0, 2.0]
+            .iter()
+            .map(|y| {
+                let steps = vec![TransformStep::Translate(Vector3d::new(5.0, *y, 0.0))];
+                let mut instance = Instance::new("ball".to_string(), TransformStack::new(steps).unwrap(), None);
+                instance.link_geometries(&geometries).unwrap();
+                instance
+            })
+            .collect();
+        assert_eq!(Arc::strong_count(&geometries["ball"]), 3);
+
+        for (instance, y) in instances.iter().zip([-2.0, 2.0].iter()) {
+            let ray = Ray::new(&Vector3d::new(0.0, *y, 0.0), &Vector3d::x_axis());
+            let hits = instance.intersect(&ray);
+            assert!((&hits[0].position - &Vector3d::new(4.0, *y, 0.0)).mag() < 1e-9);
+            assert!((instance.bounding_box().unwrap().centroid().y - y).abs() < 1e-9);
+        }
+
+        let mut unknown = Instance::new("cube".to_string(), TransformStack::default(), None);
+        assert!(unknown.link_geometries(&geometries).is_err());
+        // Nothing to hit without geometry
+        let ray = Ray::new(&Vector3d::zero(), &Vector3d::x_axis());
+        assert!(unknown.intersect(&ray).is_empty());
+        assert!(unknown.bounding_box().is_none());
+    }
+
+    #[test]
+    fn test_load_twice() {
+        let mut world = World::new(Box::new(PerspectiveCamera::default()), vec![], vec![], WHITE, 1);
+        let sphere = Sphere::new(Vector3d::zero(), 1.0, Box::new(Surface::default()));
+        world.add_geometry("ball".to_string(), Box::new(sphere));
+        world.add_thing(Box::new(Instance::new("ball".to_string(), TransformStack::default(), None)));
+        assert!(world.load_resources(Path::new(".")).is_ok());
+        assert!(matches!(world.load_resources(Path::new(".")), Err(ResourceError::GeometryShared(_))));
+    }
+}
@@ -0,0 +1,261 @@
+use std::{convert::TryFrom, path::Path};
+
+use crate::{
+    bvh::{Bvh, Hit},
+    error::{MeshError, ResourceError},
+    ray::Ray,
+    surfaces::{Material, Surface},
+    vector::{BoundingBox, Vector2d, Vector3d},
+};
+
+use super::{
+    triangle::{barycentric, face_normal, interpolate_normal, interpolate_uv, moller_trumbore, triangle_bounding_box},
+    Thing, ThingHit,
+};
+
+// Indexed buffers describing a mesh, each triangle references 3 entries of each buffer.
+// Normal and uv indices default to the vertex indices when the buffer is present.
+// Triangles use `surface` unless they reference one of the optional `materials`.
+#[derive(Serialize, Deserialize)]
+pub struct MeshData {
+    pub vertices: Vec<Vector3d>,
+    #[serde(default)]
+    pub normals: Vec<Vector3d>,
+    #[serde(default)]
+    pub uvs: Vec<Vector2d>,
+    pub indices: Vec<[usize; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normal_indices: Option<Vec<[usize; 3]>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uv_indices: Option<Vec<[usize; 3]>>,
+    pub surface: Box<dyn Material>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub materials: Vec<Box<dyn Material>>,
+    // One entry per triangle, None to use `surface`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub material_indices: Vec<Option<usize>>,
+}
+
+struct TriangleHit {
+    distance: f64,
+    position: Vector3d,
+    triangle: usize,
+}
+
+impl Hit for TriangleHit {
+    fn distance(&self) -> f64 {
+        self.distance
+    }
+}
+
+// A triangle mesh counted as one thing of the world, with its own acceleration structure.
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "MeshData")]
+pub struct Mesh {
+    #[serde(flatten)]
+    data: MeshData,
+    #[serde(skip_serializing)]
+    bvh: Bvh,
+}
+
+impl TryFrom<MeshData> for Mesh {
+    type Error = MeshError;
+
+    fn try_from(data: MeshData) -> Result<Self, Self::Error> {
+        Self::new(data)
+    }
+}
+
+fn check_indices(name: &'static str, indices: &[[usize; 3]], buffer_len: usize, triangles: usize) -> Result<(), MeshError> {
+    if indices.len() != triangles {
+        return Err(MeshError::IndexCountMismatch(name, indices.len(), triangles));
+    }
+    match indices.iter().flatten().find(|index| **index >= buffer_len) {
+        Some(index) => Err(MeshError::IndexOutOfBounds(name, *index, buffer_len)),
+        None => Ok(()),
+    }
+}
+
+// Mesh without any triangle, nothing is hit.
+impl Default for Mesh {
+    fn default() -> Self {
+        let data = MeshData {
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+            normal_indices: None,
+            uv_indices: None,
+            surface: Box::new(Surface::default()),
+            materials: Vec::new(),
+            material_indices: Vec::new(),
+        };
+        let bvh = Bvh::from_bounding_boxes(std::iter::empty());
+        Self { data, bvh }
+    }
+}
+
+impl Mesh {
+    pub fn new(data: MeshData) -> Result<Self, MeshError> {
+        let triangles = data.indices.len();
+        check_indices("vertex", &data.indices, data.vertices.len(), triangles)?;
+        if !data.normals.is_empty() {
+            let normal_indices = data.normal_indices.as_ref().unwrap_or(&data.indices);
+            check_indices("normal", normal_indices, data.normals.len(), triangles)?;
+        }
+        if !data.uvs.is_empty() {
+            let uv_indices = data.uv_indices.as_ref().unwrap_or(&data.indices);
+            check_indices("uv", uv_indices, data.uvs.len(), triangles)?;
+        }
+        if !data.material_indices.is_empty() {
+            if data.material_indices.len() != triangles {
+                return Err(MeshError::IndexCountMismatch("material", data.material_indices.len(), triangles));
+            }
+            if let Some(index) = data.material_indices.iter().flatten().find(|index| **index >= data.materials.len()) {
+                return Err(MeshError::IndexOutOfBounds("material", *index, data.materials.len()));
+            }
+        }
+
+        let bvh = Bvh::from_bounding_boxes(
+            (0..triangles).map(|triangle| Some(triangle_bounding_box(Self::vertices_of(&data, triangle)))),
+        );
+        Ok(Self { data, bvh })
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.data.indices.len()
+    }
+
+    fn vertices_of(data: &MeshData, triangle: usize) -> [&Vector3d; 3] {
+        let [a, b, c] = data.indices[triangle];
+        [&data.vertices[a], &data.vertices[b], &data.vertices[c]]
+    }
+
+    fn normals_of(&self, triangle: usize) -> Option<[&Vector3d; 3]> {
+        if self.data.normals.is_empty() {
+            return None;
+        }
+        let [a, b, c] = self.data.normal_indices.as_ref().unwrap_or(&self.data.indices)[triangle];
+        Some([&self.data.normals[a], &self.data.normals[b], &self.data.normals[c]])
+    }
+
+    fn uvs_of(&self, triangle: usize) -> Option<[&Vector2d; 3]> {
+        if self.data.uvs.is_empty() {
+            return None;
+        }
+        let [a, b, c] = self.data.uv_indices.as_ref().unwrap_or(&self.data.indices)[triangle];
+        Some([&self.data.uvs[a], &self.data.uvs[b], &self.data.uvs[c]])
+    }
+}
+
+#[typetag::serde(name = "mesh")]
+impl Thing for Mesh {
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.data.surface.load_resources(scene_dir)?;
+        self.data
+            .materials
+            .iter_mut()
+            .try_for_each(|material| material.load_resources(scene_dir))
+    }
+
+    fn normal(&self, hit: &ThingHit) -> Vector3d {
+        let vertices = Self::vertices_of(&self.data, hit.primitive);
+        self.normals_of(hit.primitive)
+            .and_then(|normals| interpolate_normal(normals, barycentric(vertices, &hit.position)))
+            .unwrap_or_else(|| face_normal(vertices))
+    }
+
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit> {
+        self.bvh
+            .closest_intersection(ray, |triangle| {
+                moller_trumbore(Self::vertices_of(&self.data, triangle), ray).map(|(t, _, _)| TriangleHit {
+                    distance: t,
+                    position: &(ray.dir() * &t.into()) + ray.start(),
+                    triangle,
+                })
+            })
+            .map(|hit| vec!(ThingHit::new(hit.position, hit.triangle)))
+            .unwrap_or_default()
+    }
+
+    fn surface(&self) -> &dyn Material {
+        self.data.surface.as_ref()
+    }
+
+    fn surface_at(&self, hit: &ThingHit) -> &dyn Material {
+        if self.data.material_indices.is_empty() {
+            return self.data.surface.as_ref();
+        }
+        match self.data.material_indices[hit.primitive] {
+            Some(material) => self.data.materials[material].as_ref(),
+            None => self.data.surface.as_ref(),
+        }
+    }
+
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d {
+        let weights = barycentric(Self::vertices_of(&self.data, hit.primitive), &hit.position);
+        match self.uvs_of(hit.primitive) {
+            Some(uvs) => interpolate_uv(uvs, weights),
+            None => Vector2d::new(weights.1, weights.2),
+        }
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let mut vertices = self.data.vertices.iter();
+        let first = BoundingBox::from_point(vertices.next()?);
+        Some(vertices.fold(first, |bounding_box, vertex| bounding_box.grow(vertex)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_worlds::surface_json;
+
+    // A unit square in the x = 2 plane made of 2 triangles.
+    fn square_json(indices: &str) -> String {
+        format!(
+            r#"{{
+            "type": "mesh",
+            "vertices": [
+                {{ "x": 2.0, "y": -1.0, "z": -1.0 }},
+                {{ "x": 2.0, "y": 1.0, "z": -1.0 }},
+                {{ "x": 2.0, "y": 1.0, "z": 1.0 }},
+                {{ "x": 2.0, "y": -1.0, "z": 1.0 }}
+            ],
+            "uvs": [ {{ "x": 0.0, "y": 0.0 }}, {{ "x": 1.0, "y": 0.0 }}, {{ "x": 1.0, "y": 1.0 }}, {{ "x": 0.0, "y": 1.0 }} ],
+            "indices": {},
+            "surface": {}
+        }}"#,
+            indices,
+            surface_json(0.0)
+        )
+    }
+
+    #[test]
+    fn test_intersect_square() {
+        let mesh: Box<dyn Thing> = serde_json::from_str(&square_json("[[0, 1, 2], [0, 2, 3]]")).unwrap();
+        let ray = Ray::new(&Vector3d::zero(), &Vector3d::new(2.0, -0.5, 0.5));
+        let hits = mesh.intersect(&ray);
+        assert_eq!(hits, vec!(ThingHit::new(Vector3d::new(2.0, -0.5, 0.5), 1)));
+
+        let normal = mesh.normal(&hits[0]);
+        assert!((normal - Vector3d::x_axis()).mag() < 1e-9);
+
+        let uv = mesh.get_uv_mapping(&hits[0]);
+        assert!((uv.x - 0.25).abs() < 1e-9 && (uv.y - 0.75).abs() < 1e-9);
+
+        let miss = Ray::new(&Vector3d::zero(), &Vector3d::new(2.0, -1.5, 0.5));
+        assert!(mesh.intersect(&miss).is_empty());
+
+        let json = serde_json::to_string(&mesh).unwrap();
+        let reloaded: Box<dyn Thing> = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.intersect(&ray), hits);
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let mesh: Result<Box<dyn Thing>, _> = serde_json::from_str(&square_json("[[0, 1, 4]]"));
+        assert!(mesh.is_err());
+    }
+}
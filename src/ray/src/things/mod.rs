@@ -3,3 +3,21 @@ pub use thing::*;
 
 mod sphere;
 pub use sphere::*;
+
+mod plane;
+pub use plane::*;
+
+mod triangle;
+pub use triangle::*;
+
+mod mesh;
+pub use mesh::*;
+
+mod obj_mesh;
+pub use obj_mesh::*;
+
+mod transformed;
+pub use transformed::*;
+
+mod instance;
+pub use instance::*;
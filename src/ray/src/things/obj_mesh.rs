@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::ResourceError,
+    loaders::load_obj,
+    ray::Ray,
+    surfaces::Material,
+    vector::{BoundingBox, Vector2d, Vector3d},
+};
+
+use super::{Mesh, Thing, ThingHit};
+
+// A mesh loaded from a Wavefront obj file when the world resources are loaded, empty until then.
+#[derive(Serialize, Deserialize)]
+pub struct ObjMesh {
+    // Relative to the scene file directory
+    path: PathBuf,
+    // Replaces the materials of the obj file when given
+    #[serde(default)]
+    surface: Option<Box<dyn Material>>,
+    #[serde(skip)]
+    mesh: Mesh,
+}
+
+impl ObjMesh {
+    pub fn new(path: PathBuf, surface: Option<Box<dyn Material>>) -> Self {
+        Self {
+            path,
+            surface,
+            mesh: Mesh::default(),
+        }
+    }
+}
+
+#[typetag::serde(name = "obj_mesh")]
+impl Thing for ObjMesh {
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        let data = load_obj(&scene_dir.join(&self.path))?;
+        self.mesh = Mesh::new(data)?;
+        match self.surface.as_mut() {
+            Some(surface) => surface.load_resources(scene_dir),
+            None => Ok(()),
+        }
+    }
+
+    fn normal(&self, hit: &ThingHit) -> Vector3d {
+        self.mesh.normal(hit)
+    }
+
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit> {
+        self.mesh.intersect(ray)
+    }
+
+    fn surface(&self) -> &dyn Material {
+        match &self.surface {
+            Some(surface) => surface.as_ref(),
+            None => self.mesh.surface(),
+        }
+    }
+
+    fn surface_at(&self, hit: &ThingHit) -> &dyn Material {
+        match &self.surface {
+            Some(surface) => surface.as_ref(),
+            None => self.mesh.surface_at(hit),
+        }
+    }
+
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d {
+        self.mesh.get_uv_mapping(hit)
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.mesh.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_loaded() {
+        // Nothing is hit before the world resources are loaded
+        let obj: Box<dyn Thing> = serde_json::from_str(r#"{ "type": "obj_mesh", "path": "teapot.obj" }"#).unwrap();
+        assert!(obj.intersect(&Ray::new(&Vector3d::zero(), &Vector3d::x_axis())).is_empty());
+        assert!(obj.bounding_box().is_none());
+    }
+}
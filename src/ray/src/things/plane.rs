@@ -0,0 +1,170 @@
+use std::{convert::TryFrom, path::Path};
+
+use crate::{
+    error::{GeometryError, ResourceError},
+    intersection::EPSILON,
+    ray::Ray,
+    surfaces::Material,
+    vector::{Vector2d, Vector3d},
+};
+
+use super::{Thing, ThingHit};
+
+fn default_tile_size() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct PlaneSpec {
+    position: Vector3d,
+    normal: Vector3d,
+    surface: Box<dyn Material>,
+    #[serde(default = "default_tile_size")]
+    tile_size: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "PlaneSpec")]
+pub struct Plane {
+    position: Vector3d,
+    // Unit vector
+    normal: Vector3d,
+    surface: Box<dyn Material>,
+    // Length of a UV tile along the two in-plane axes.
+    tile_size: f64,
+}
+
+impl TryFrom<PlaneSpec> for Plane {
+    type Error = GeometryError;
+
+    fn try_from(spec: PlaneSpec) -> Result<Self, Self::Error> {
+        Ok(Self::new(spec.position, spec.normal, spec.surface)?.with_tile_size(spec.tile_size))
+    }
+}
+
+impl Plane {
+    pub fn new(position: Vector3d, normal: Vector3d, surface: Box<dyn Material>) -> Result<Self, GeometryError> {
+        Ok(Self {
+            position,
+            normal: normal.norm().map_err(|_| GeometryError::ZeroVector("normal of a plane"))?,
+            surface,
+            tile_size: default_tile_size(),
+        })
+    }
+
+    pub fn with_tile_size(mut self, tile_size: f64) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    // Two orthonormal vectors lying in the plane.
+    fn in_plane_axes(&self) -> (Vector3d, Vector3d) {
+        let normal = &self.normal;
+        let helper = if normal.x.abs() < 0.9 {
+            Vector3d::x_axis()
+        } else {
+            Vector3d::y_axis()
+        };
+        let u_axis = normal.cross(helper).norm().unwrap();
+        let v_axis = normal.cross(u_axis.clone());
+        (u_axis, v_axis)
+    }
+}
+
+#[typetag::serde(name = "plane")]
+impl Thing for Plane {
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.surface.load_resources(scene_dir)
+    }
+
+    fn normal(&self, _hit: &ThingHit) -> Vector3d {
+        self.normal.clone()
+    }
+
+    // https://www.scratchapixel.com/lessons/3d-basic-rendering/minimal-ray-tracer-rendering-simple-shapes/ray-plane-and-ray-disk-intersection
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit> {
+        let normal = &self.normal;
+        let denom = normal.dot(ray.dir());
+        if denom.abs() < EPSILON {
+            return vec!();
+        }
+        let t = (&self.position - ray.start()).dot(normal) / denom;
+        if t < 0.0 {
+            return vec!();
+        }
+        vec!((&(ray.dir() * &t.into()) + ray.start()).into())
+    }
+
+    fn surface(&self) -> &dyn Material {
+        self.surface.as_ref()
+    }
+
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d {
+        let (u_axis, v_axis) = self.in_plane_axes();
+        let local = &hit.position - &self.position;
+        Vector2d::new(
+            (local.dot(&u_axis) / self.tile_size).rem_euclid(1.0),
+            (local.dot(&v_axis) / self.tile_size).rem_euclid(1.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_worlds::surface_json;
+
+    fn floor() -> Box<dyn Thing> {
+        let json = format!(
+            r#"{{
+            "type": "plane",
+            "position": {{ "x": 0.0, "y": 0.0, "z": -1.0 }},
+            "normal": {{ "x": 0.0, "y": 0.0, "z": 2.0 }},
+            "tile_size": 0.5,
+            "surface": {}
+        }}"#,
+            surface_json(0.0)
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_intersect() {
+        let plane = floor();
+        let ray = Ray::new(&Vector3d::zero(), &Vector3d::new(1.0, 0.0, -1.0));
+        assert_eq!(plane.intersect(&ray), vec!(Vector3d::new(1.0, 0.0, -1.0).into()));
+        assert_eq!(plane.normal(&Vector3d::zero().into()), Vector3d::z_axis());
+        assert!(plane.bounding_box().is_none());
+
+        let parallel = Ray::new(&Vector3d::zero(), &Vector3d::x_axis());
+        assert!(plane.intersect(&parallel).is_empty());
+
+        let away = Ray::new(&Vector3d::zero(), &Vector3d::z_axis());
+        assert!(plane.intersect(&away).is_empty());
+    }
+
+    #[test]
+    fn test_zero_normal() {
+        let json = format!(
+            r#"{{
+            "type": "plane",
+            "position": {{ "x": 0.0, "y": 0.0, "z": -1.0 }},
+            "normal": {{ "x": 0.0, "y": 0.0, "z": 0.0 }},
+            "surface": {}
+        }}"#,
+            surface_json(0.0)
+        );
+        let error = serde_json::from_str::<Box<dyn Thing>>(&json).err().unwrap().to_string();
+        assert!(error.contains("normal of a plane"), "{}", error);
+    }
+
+    #[test]
+    fn test_uv_tiles() {
+        let plane = floor();
+        let uv = plane.get_uv_mapping(&Vector3d::new(0.1, 0.2, -1.0).into());
+        let same_uv = plane.get_uv_mapping(&Vector3d::new(0.6, 1.7, -1.0).into());
+        assert!(uv.x >= 0.0 && uv.x < 1.0 && uv.y >= 0.0 && uv.y < 1.0);
+        assert!((uv.x - same_uv.x).abs() < 1e-9);
+        assert!((uv.y - same_uv.y).abs() < 1e-9);
+    }
+}
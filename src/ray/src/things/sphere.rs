@@ -1,31 +1,36 @@
-use crate::{ray::Ray, surfaces::Surface, vector::{Vector3d,Vector2d}};
+use std::{f64::consts::PI, path::Path};
 
-use super::Thing;
+use crate::{error::ResourceError, ray::Ray, surfaces::Material, vector::{BoundingBox, Vector3d, Vector2d}};
+
+use super::{Thing, ThingHit};
 
 #[derive(Serialize, Deserialize)]
 pub struct Sphere {
     radius: f64,
     position: Vector3d,
-    surface: Surface,
+    surface: Box<dyn Material>,
 }
 
 impl Sphere {
-    pub fn new(position: Vector3d, radius: f64, surface: Surface) -> Self {
+    pub fn new(position: Vector3d, radius: f64, surface: Box<dyn Material>) -> Self {
         Self { position, radius , surface}
     }
 }
 
 #[typetag::serde(name = "sphere")]
 impl Thing for Sphere {
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.surface.load_resources(scene_dir)
+    }
 
-    fn normal(&self, position: &Vector3d) -> Vector3d {
-        (position.clone() - self.position.clone()).norm().unwrap()
+    fn normal(&self, hit: &ThingHit) -> Vector3d {
+        (hit.position.clone() - self.position.clone()).norm().unwrap()
     }
 
         // https://www.scratchapixel.com/lessons/3d-basic-rendering/minimal-ray-tracer-rendering-simple-shapes/ray-sphere-intersection
-    fn intersect(&self, ray: &Ray) -> Vec<Vector3d> {
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit> {
         // let l: Vector = self.position.clone() - ray.start().clone();
-        // let adj = l.dot(&ray.dir());
+        // let adj = l.dot(ray.dir());
         // let d2 = l.dot(&l) - (adj * adj);
         // let radius2 = self.radius * self.radius;
         // if d2 > radius2 {
@@ -50,7 +55,7 @@ impl Thing for Sphere {
 
 
         let l: Vector3d = self.position.clone() - ray.start().clone();
-        let adj = l.dot(&ray.dir());
+        let adj = l.dot(ray.dir());
         let d2 = l.dot(&l) - (adj * adj);
         let radius2 = self.radius * self.radius;
         if d2 > radius2 {
@@ -60,16 +65,49 @@ impl Thing for Sphere {
         let t0 = adj - thc;
         let t1 = adj + thc;
  
-        vec!(&(ray.dir() * &t0.into()) + ray.start(),
-        &(ray.dir() * &t1.into()) + ray.start())
+        vec!((&(ray.dir() * &t0.into()) + ray.start()).into(),
+        (&(ray.dir() * &t1.into()) + ray.start()).into())
+    }
+
+    fn surface(&self) -> &dyn Material {
+        self.surface.as_ref()
     }
 
-    fn surface(&self) -> &Surface {
-        &self.surface
+    // Longitude and latitude: u goes around the z axis from the -x side, v from the
+    // bottom pole (0) to the top pole (1).
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d {
+        let local = (&hit.position - &self.position).each_div(self.radius);
+        let u = 0.5 + local.y.atan2(local.x) / (2.0 * PI);
+        let v = 0.5 + local.z.clamp(-1.0, 1.0).asin() / PI;
+        Vector2d::new(u, v)
     }
 
-    fn get_uv_mapping(&self, _position: &Vector3d) -> Vector2d {
-        //todo!("Uv mapping not implemented")
-        0.0.into()
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(BoundingBox::new(
+            self.position.each_sub(self.radius),
+            self.position.each_add(self.radius),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::surfaces::Surface;
+
+    #[test]
+    fn test_uv_mapping() {
+        let sphere = Sphere::new(Vector3d::new(1.0, 2.0, 3.0), 2.0, Box::new(Surface::default()));
+        let uv_at = |x: f64, y: f64, z: f64| sphere.get_uv_mapping(&Vector3d::new(1.0 + x, 2.0 + y, 3.0 + z).into());
+        let assert_uv = |uv: Vector2d, u: f64, v: f64| {
+            assert!((uv.x - u).abs() < 1e-9 && (uv.y - v).abs() < 1e-9, "{} instead of ({}, {})", uv, u, v);
+        };
+        assert_uv(uv_at(2.0, 0.0, 0.0), 0.5, 0.5);
+        assert_uv(uv_at(0.0, 2.0, 0.0), 0.75, 0.5);
+        assert_uv(uv_at(0.0, -2.0, 0.0), 0.25, 0.5);
+        assert_eq!(uv_at(0.0, 0.0, 2.0).y, 1.0);
+        assert_eq!(uv_at(0.0, 0.0, -2.0).y, 0.0);
+        let latitude_45 = uv_at(2f64.sqrt(), 0.0, 2f64.sqrt());
+        assert_uv(latitude_45, 0.5, 0.75);
     }
 }
@@ -1,32 +1,64 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
 use crate::{
-    color::Color,
+    error::ResourceError,
     ray::Ray,
-    surfaces::Surface,
-    vector::{Vector2d, Vector3d},
+    surfaces::Material,
+    vector::{BoundingBox, Vector2d, Vector3d},
 };
 
+// Point where a ray meets a thing. `primitive` is the part of the thing that was hit (the
+// triangle of a mesh) so that the shading does not have to find it again, 0 for the things
+// made of one piece.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThingHit {
+    pub position: Vector3d,
+    pub primitive: usize,
+}
+
+impl ThingHit {
+    pub fn new(position: Vector3d, primitive: usize) -> Self {
+        Self { position, primitive }
+    }
+}
+
+impl From<Vector3d> for ThingHit {
+    fn from(position: Vector3d) -> Self {
+        Self::new(position, 0)
+    }
+}
+
 #[typetag::serde(tag = "type")]
-pub trait Thing {
-    fn intersect(&self, ray: &Ray) -> Vec<Vector3d>;
-    fn surface(&self) -> &Surface;
-    fn normal(&self, position: &Vector3d) -> Vector3d;
+pub trait Thing: Send + Sync {
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit>;
+    fn surface(&self) -> &dyn Material;
+    fn normal(&self, hit: &ThingHit) -> Vector3d;
 
-    fn get_uv_mapping(&self, position: &Vector3d) -> Vector2d;
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d;
 
-    fn ambiant(&self, position: &Vector3d) -> Color {
-        self.surface().ambiant(&self.get_uv_mapping(position))
+    // Position in the space of the thing before its transforms, read by the solid textures.
+    fn object_position(&self, hit: &ThingHit) -> Vector3d {
+        hit.position.clone()
     }
-    fn diffuse(&self, position: &Vector3d) -> Color {
-        self.surface().diffuse(&self.get_uv_mapping(position))
+
+    // None for unbounded things (infinite planes...), they are always tested.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        None
     }
-    fn specular(&self, position: &Vector3d) -> Color {
-        self.surface().specular(&self.get_uv_mapping(position))
+
+    // Loads external files (models...), paths are relative to the scene file directory.
+    fn load_resources(&mut self, _scene_dir: &Path) -> Result<(), ResourceError> {
+        Ok(())
     }
-    fn refraction(&self, position: &Vector3d) -> Color {
-        self.surface().refraction(&self.get_uv_mapping(position))
+
+    // Resolves the references to the named geometries shared by the world,
+    // called after all the resources are loaded.
+    fn link_geometries(&mut self, _geometries: &HashMap<String, Arc<dyn Thing>>) -> Result<(), ResourceError> {
+        Ok(())
     }
 
-    fn refraction_ratio(&self) -> f64 {
-        self.surface().refraction_ratio()
+    // Things made of several materials return the surface of the part that was hit.
+    fn surface_at(&self, _hit: &ThingHit) -> &dyn Material {
+        self.surface()
     }
 }
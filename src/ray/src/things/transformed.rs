@@ -0,0 +1,249 @@
+use std::{collections::HashMap, convert::TryFrom, path::Path, sync::Arc};
+
+use math::{error::MatrixError, matrix4::Mat4};
+
+use crate::{
+    error::ResourceError,
+    ray::Ray,
+    surfaces::Material,
+    vector::{BoundingBox, Transform, Vector2d, Vector3d},
+};
+
+use super::{Thing, ThingHit};
+
+// One operation of a transform stack, as written in the scene file.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformStep {
+    Translate(Vector3d),
+    Rotate { axis: Vector3d, degrees: f64 },
+    Scale(Vector3d),
+    Matrix(Mat4<f64>),
+}
+
+impl TransformStep {
+    fn to_transform(&self) -> Result<Transform, MatrixError> {
+        match self {
+            TransformStep::Translate(offset) => Ok(Transform::translation(offset)),
+            TransformStep::Rotate { axis, degrees } => Ok(Transform::rotation(axis, degrees.to_radians())),
+            TransformStep::Scale(scale) => Transform::scaling(scale),
+            TransformStep::Matrix(matrix) => Transform::new(matrix.clone()),
+        }
+    }
+}
+
+// Steps are applied in the order they are listed, mapping the object space to the world.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "Vec<TransformStep>", into = "Vec<TransformStep>")]
+pub struct TransformStack {
+    steps: Vec<TransformStep>,
+    transform: Transform,
+}
+
+impl TryFrom<Vec<TransformStep>> for TransformStack {
+    type Error = MatrixError;
+
+    fn try_from(steps: Vec<TransformStep>) -> Result<Self, Self::Error> {
+        Self::new(steps)
+    }
+}
+
+impl From<TransformStack> for Vec<TransformStep> {
+    fn from(stack: TransformStack) -> Self {
+        stack.steps
+    }
+}
+
+impl TransformStack {
+    pub fn new(steps: Vec<TransformStep>) -> Result<Self, MatrixError> {
+        let transform = steps.iter().try_fold(Transform::identity(), |transform, step| {
+            Ok(transform.then(&step.to_transform()?))
+        })?;
+        Ok(Self { steps, transform })
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn ray_to_object(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            &self.transform.inverse_transform_point(ray.start()),
+            &self.transform.inverse_transform_vector(ray.dir()),
+        )
+    }
+
+    pub fn position_to_world(&self, position: &Vector3d) -> Vector3d {
+        self.transform.transform_point(position)
+    }
+
+    // The hit keeps its primitive, only the position changes of space.
+    pub fn hit_to_object(&self, hit: &ThingHit) -> ThingHit {
+        ThingHit::new(self.transform.inverse_transform_point(&hit.position), hit.primitive)
+    }
+
+    pub fn hit_to_world(&self, hit: &ThingHit) -> ThingHit {
+        ThingHit::new(self.position_to_world(&hit.position), hit.primitive)
+    }
+
+    pub fn normal_to_world(&self, normal: &Vector3d) -> Vector3d {
+        let normal = self.transform.transform_normal(normal);
+        normal.norm().unwrap_or(normal)
+    }
+
+    // Box containing the 8 transformed corners of an object space box.
+    pub fn bounding_box_to_world(&self, bounding_box: &BoundingBox) -> BoundingBox {
+        let (min, max) = (&bounding_box.min, &bounding_box.max);
+        let corner = |i: usize| {
+            Vector3d::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        (1..8).fold(
+            BoundingBox::from_point(&self.position_to_world(&corner(0))),
+            |world_box, i| world_box.grow(&self.position_to_world(&corner(i))),
+        )
+    }
+}
+
+// Any thing moved, rotated or scaled by a transform stack.
+#[derive(Serialize, Deserialize)]
+pub struct Transformed {
+    transform: TransformStack,
+    thing: Box<dyn Thing>,
+}
+
+impl Transformed {
+    pub fn new(transform: TransformStack, thing: Box<dyn Thing>) -> Self {
+        Self { transform, thing }
+    }
+}
+
+#[typetag::serde(name = "transformed")]
+impl Thing for Transformed {
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.thing.load_resources(scene_dir)
+    }
+
+    fn link_geometries(&mut self, geometries: &HashMap<String, Arc<dyn Thing>>) -> Result<(), ResourceError> {
+        self.thing.link_geometries(geometries)
+    }
+
+    fn normal(&self, hit: &ThingHit) -> Vector3d {
+        let normal = self.thing.normal(&self.transform.hit_to_object(hit));
+        self.transform.normal_to_world(&normal)
+    }
+
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit> {
+        self.thing
+            .intersect(&self.transform.ray_to_object(ray))
+            .iter()
+            .map(|hit| self.transform.hit_to_world(hit))
+            .collect()
+    }
+
+    fn surface(&self) -> &dyn Material {
+        self.thing.surface()
+    }
+
+    fn surface_at(&self, hit: &ThingHit) -> &dyn Material {
+        self.thing.surface_at(&self.transform.hit_to_object(hit))
+    }
+
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d {
+        self.thing.get_uv_mapping(&self.transform.hit_to_object(hit))
+    }
+
+    fn object_position(&self, hit: &ThingHit) -> Vector3d {
+        self.thing.object_position(&self.transform.hit_to_object(hit))
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.thing
+            .bounding_box()
+            .map(|bounding_box| self.transform.bounding_box_to_world(&bounding_box))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{surfaces::Surface, test_worlds::surface_json, things::Sphere};
+
+    fn ellipsoid() -> Transformed {
+        let sphere = Sphere::new(Vector3d::zero(), 1.0, Box::new(Surface::default()));
+        let steps = vec![
+            TransformStep::Scale(Vector3d::new(1.0, 1.0, 3.0)),
+            TransformStep::Translate(Vector3d::new(5.0, 0.0, 0.0)),
+        ];
+        Transformed::new(TransformStack::new(steps).unwrap(), Box::new(sphere))
+    }
+
+    #[test]
+    fn test_intersect_and_normal() {
+        let thing = ellipsoid();
+        // The sphere is stretched along z
+        let ray = Ray::new(&Vector3d::new(5.0, 0.0, 10.0), &Vector3d::new(0.0, 0.0, -1.0));
+        let hits = thing.intersect(&ray);
+        assert_eq!(hits.len(), 2);
+        assert!((&hits[0].position - &Vector3d::new(5.0, 0.0, 3.0)).mag() < 1e-9);
+        assert!((&thing.normal(&hits[0]) - &Vector3d::z_axis()).mag() < 1e-9);
+
+        let ray = Ray::new(&Vector3d::new(5.0, 0.0, 2.0), &Vector3d::new(0.0, 1.0, 0.0));
+        let hits = thing.intersect(&ray);
+        assert!(hits.iter().all(|hit| hit.position.y.abs() < 1.0));
+
+        let bounding_box = thing.bounding_box().unwrap();
+        assert!((&bounding_box.min - &Vector3d::new(4.0, -1.0, -3.0)).mag() < 1e-9);
+        assert!((&bounding_box.max - &Vector3d::new(6.0, 1.0, 3.0)).mag() < 1e-9);
+    }
+
+    #[test]
+    fn test_normal_stays_perpendicular() {
+        let thing = ellipsoid();
+        // Point of the ellipsoid x²/1 + z²/9 = 1 (y = 0) around the center
+        let angle: f64 = 0.7;
+        let position = Vector3d::new(5.0 + angle.cos(), 0.0, 3.0 * angle.sin());
+        let tangent = Vector3d::new(-angle.sin(), 0.0, 3.0 * angle.cos());
+        assert!(thing.normal(&position.into()).dot(&tangent).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_object_position() {
+        let thing = ellipsoid();
+        let position = thing.object_position(&Vector3d::new(5.0, 0.0, 3.0).into());
+        assert!((&position - &Vector3d::z_axis()).mag() < 1e-9);
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = format!(
+            r#"{{
+            "type": "transformed",
+            "transform": [
+                {{ "rotate": {{ "axis": {{ "x": 0.0, "y": 0.0, "z": 1.0 }}, "degrees": 90.0 }} }},
+                {{ "translate": {{ "x": 1.0, "y": 0.0, "z": 0.0 }} }}
+            ],
+            "thing": {{
+                "type": "sphere",
+                "radius": 0.5,
+                "position": {{ "x": 1.0, "y": 0.0, "z": 0.0 }},
+                "surface": {}
+            }}
+        }}"#,
+            surface_json(0.5)
+        );
+        let thing: Box<dyn Thing> = serde_json::from_str(&json).unwrap();
+        let bounding_box = thing.bounding_box().unwrap();
+        // (1, 0, 0) rotated to (0, 1, 0) then moved to (1, 1, 0)
+        assert!((&bounding_box.centroid() - &Vector3d::new(1.0, 1.0, 0.0)).mag() < 1e-9);
+
+        let round_trip = serde_json::to_string(&thing).unwrap();
+        assert!(serde_json::from_str::<Box<dyn Thing>>(&round_trip).is_ok());
+
+        let singular = json.replace(r#""degrees": 90.0 } }"#, r#""degrees": 90.0 } }, { "scale": { "x": 0.0, "y": 1.0, "z": 1.0 } }"#);
+        assert!(serde_json::from_str::<Box<dyn Thing>>(&singular).is_err());
+    }
+}
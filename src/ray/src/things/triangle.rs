@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use crate::{
+    error::ResourceError,
+    intersection::EPSILON,
+    ray::Ray,
+    surfaces::Material,
+    vector::{BoundingBox, Vector2d, Vector3d},
+};
+
+use super::{Thing, ThingHit};
+
+// https://www.scratchapixel.com/lessons/3d-basic-rendering/ray-tracing-rendering-a-triangle/moller-trumbore-ray-triangle-intersection
+// Returns the distance along the ray and the barycentric coordinates (u, v) of the hit,
+// the weight of the first vertex being 1 - u - v.
+pub(crate) fn moller_trumbore(vertices: [&Vector3d; 3], ray: &Ray) -> Option<(f64, f64, f64)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let p = ray.dir().cross(edge2.clone());
+    let det = edge1.dot(&p);
+    if det.abs() < f64::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = ray.start() - vertices[0];
+    let u = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(edge1);
+    let v = ray.dir().dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(&q) * inv_det;
+    if t > EPSILON {
+        Some((t, u, v))
+    } else {
+        None
+    }
+}
+
+// Barycentric coordinates (w0, w1, w2) of a point lying in the plane of the triangle.
+pub(crate) fn barycentric(vertices: [&Vector3d; 3], position: &Vector3d) -> (f64, f64, f64) {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let to_point = position - vertices[0];
+    let d11 = edge1.dot(&edge1);
+    let d12 = edge1.dot(&edge2);
+    let d22 = edge2.dot(&edge2);
+    let dp1 = to_point.dot(&edge1);
+    let dp2 = to_point.dot(&edge2);
+    let denom = d11 * d22 - d12 * d12;
+    if denom.abs() < f64::EPSILON {
+        return (1.0, 0.0, 0.0);
+    }
+    let w1 = (d22 * dp1 - d12 * dp2) / denom;
+    let w2 = (d11 * dp2 - d12 * dp1) / denom;
+    (1.0 - w1 - w2, w1, w2)
+}
+
+pub(crate) fn face_normal(vertices: [&Vector3d; 3]) -> Vector3d {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    edge1.cross(edge2).norm().unwrap_or_else(|_| Vector3d::z_axis())
+}
+
+pub(crate) fn interpolate_normal(normals: [&Vector3d; 3], weights: (f64, f64, f64)) -> Option<Vector3d> {
+    (normals[0] * &weights.0.into() + normals[1] * &weights.1.into() + normals[2] * &weights.2.into())
+        .norm()
+        .ok()
+}
+
+pub(crate) fn interpolate_uv(uvs: [&Vector2d; 3], weights: (f64, f64, f64)) -> Vector2d {
+    Vector2d::new(
+        uvs[0].x * weights.0 + uvs[1].x * weights.1 + uvs[2].x * weights.2,
+        uvs[0].y * weights.0 + uvs[1].y * weights.1 + uvs[2].y * weights.2,
+    )
+}
+
+pub(crate) fn triangle_bounding_box(vertices: [&Vector3d; 3]) -> BoundingBox {
+    BoundingBox::from_point(vertices[0])
+        .grow(vertices[1])
+        .grow(vertices[2])
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Triangle {
+    vertices: [Vector3d; 3],
+    // Per vertex normals for smooth shading, the face normal is used otherwise.
+    #[serde(default)]
+    normals: Option<[Vector3d; 3]>,
+    #[serde(default)]
+    uvs: Option<[Vector2d; 3]>,
+    surface: Box<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vector3d; 3], surface: Box<dyn Material>) -> Self {
+        Self {
+            vertices,
+            normals: None,
+            uvs: None,
+            surface,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vector3d; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [Vector2d; 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+
+    fn vertex_refs(&self) -> [&Vector3d; 3] {
+        [&self.vertices[0], &self.vertices[1], &self.vertices[2]]
+    }
+}
+
+#[typetag::serde(name = "triangle")]
+impl Thing for Triangle {
+    fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.surface.load_resources(scene_dir)
+    }
+
+    fn normal(&self, hit: &ThingHit) -> Vector3d {
+        self.normals
+            .as_ref()
+            .and_then(|n| interpolate_normal([&n[0], &n[1], &n[2]], barycentric(self.vertex_refs(), &hit.position)))
+            .unwrap_or_else(|| face_normal(self.vertex_refs()))
+    }
+
+    fn intersect(&self, ray: &Ray) -> Vec<ThingHit> {
+        match moller_trumbore(self.vertex_refs(), ray) {
+            Some((t, _, _)) => vec!((&(ray.dir() * &t.into()) + ray.start()).into()),
+            None => vec!(),
+        }
+    }
+
+    fn surface(&self) -> &dyn Material {
+        self.surface.as_ref()
+    }
+
+    fn get_uv_mapping(&self, hit: &ThingHit) -> Vector2d {
+        let weights = barycentric(self.vertex_refs(), &hit.position);
+        match &self.uvs {
+            Some(uvs) => interpolate_uv([&uvs[0], &uvs[1], &uvs[2]], weights),
+            None => Vector2d::new(weights.1, weights.2),
+        }
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(triangle_bounding_box(self.vertex_refs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::BLACK, test_worlds::diffuse_surface};
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            [
+                Vector3d::new(2.0, -1.0, -1.0),
+                Vector3d::new(2.0, 1.0, -1.0),
+                Vector3d::new(2.0, 0.0, 1.0),
+            ],
+            diffuse_surface(BLACK),
+        )
+    }
+
+    #[test]
+    fn test_intersect() {
+        let t = triangle();
+        let hit = Ray::new(&Vector3d::zero(), &Vector3d::x_axis());
+        assert_eq!(t.intersect(&hit), vec!(Vector3d::new(2.0, 0.0, 0.0).into()));
+
+        let miss = Ray::new(&Vector3d::zero(), &Vector3d::new(1.0, 0.0, 1.0));
+        assert!(t.intersect(&miss).is_empty());
+
+        let behind = Ray::new(&Vector3d::new(3.0, 0.0, 0.0), &Vector3d::x_axis());
+        assert!(t.intersect(&behind).is_empty());
+    }
+
+    #[test]
+    fn test_normals() {
+        let flat = triangle();
+        assert_eq!(flat.normal(&Vector3d::new(2.0, 0.0, 0.0).into()), Vector3d::x_axis());
+
+        let smooth = triangle().with_normals([
+            Vector3d::new(-1.0, -1.0, 0.0),
+            Vector3d::new(-1.0, 1.0, 0.0),
+            -Vector3d::x_axis(),
+        ]);
+        let at_first_vertex = smooth.normal(&Vector3d::new(2.0, -1.0, -1.0).into());
+        assert!((at_first_vertex - Vector3d::new(-1.0, -1.0, 0.0).norm().unwrap()).mag() < 1e-9);
+    }
+
+    #[test]
+    fn test_uvs() {
+        let t = triangle().with_uvs([
+            Vector2d::new(0.0, 0.0),
+            Vector2d::new(1.0, 0.0),
+            Vector2d::new(0.5, 1.0),
+        ]);
+        let uv = t.get_uv_mapping(&Vector3d::new(2.0, 0.0, 1.0).into());
+        assert!((uv.x - 0.5).abs() < 1e-9 && (uv.y - 1.0).abs() < 1e-9);
+    }
+}
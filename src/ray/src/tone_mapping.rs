@@ -0,0 +1,188 @@
+use std::str::FromStr;
+
+use crate::{color::Color, image::Image};
+
+// Curve bringing the linear radiance of each channel to [0, 1].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ToneMapping {
+    // Values above 1 are cut
+    #[default]
+    Clamp,
+    // x / (1 + x), never reaches 1
+    Reinhard,
+    // Reinhard reaching 1 at the white point
+    ExtendedReinhard,
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // Hable's filmic curve from Uncharted 2
+    Uncharted2,
+}
+
+impl ToneMapping {
+    pub const NAMES: [&'static str; 5] = ["clamp", "reinhard", "extended_reinhard", "aces", "uncharted2"];
+
+    fn map(&self, x: f64, white: f64) -> f64 {
+        match self {
+            ToneMapping::Clamp => x,
+            ToneMapping::Reinhard => x / (1.0 + x),
+            ToneMapping::ExtendedReinhard => x * (1.0 + x / (white * white)) / (1.0 + x),
+            ToneMapping::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+            // http://filmicworlds.com/blog/filmic-tonemapping-operators/
+            ToneMapping::Uncharted2 => {
+                let hable = |x: f64| {
+                    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+                    (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+                };
+                hable(2.0 * x) / hable(11.2)
+            }
+        }
+    }
+}
+
+impl FromStr for ToneMapping {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "clamp" => Ok(ToneMapping::Clamp),
+            "reinhard" => Ok(ToneMapping::Reinhard),
+            "extended_reinhard" => Ok(ToneMapping::ExtendedReinhard),
+            "aces" => Ok(ToneMapping::Aces),
+            "uncharted2" => Ok(ToneMapping::Uncharted2),
+            _ => Err(format!("Unknown tone mapping: {}", name)),
+        }
+    }
+}
+
+fn srgb_encode(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        12.92 * value
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Output stage turning the linear image of the engine into displayable values: the exposure
+// is applied first, then the tone mapping and the optional sRGB encoding. The default one
+// only clamps the values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToneMapper {
+    pub operator: ToneMapping,
+    // In stops, the radiance is multiplied by 2^exposure
+    pub exposure: f64,
+    // Radiance mapped to 1 by the extended Reinhard operator
+    pub white: f64,
+    pub srgb: bool,
+}
+
+impl Default for ToneMapper {
+    fn default() -> Self {
+        Self::new(ToneMapping::default())
+    }
+}
+
+impl ToneMapper {
+    pub fn new(operator: ToneMapping) -> Self {
+        Self {
+            operator,
+            exposure: 0.0,
+            white: 4.0,
+            srgb: false,
+        }
+    }
+
+    pub fn with_exposure(self, exposure: f64) -> Self {
+        Self { exposure, ..self }
+    }
+
+    pub fn with_white(self, white: f64) -> Self {
+        Self { white, ..self }
+    }
+
+    pub fn with_srgb(self, srgb: bool) -> Self {
+        Self { srgb, ..self }
+    }
+
+    pub fn map_color(&self, color: &Color) -> Color {
+        let scale = 2f64.powf(self.exposure);
+        let map = |value: f64| {
+            let mapped = self.operator.map(value * scale, self.white).clamp(0.0, 1.0);
+            if self.srgb {
+                srgb_encode(mapped)
+            } else {
+                mapped
+            }
+        };
+        Color::new(map(color.r), map(color.g), map(color.b))
+    }
+
+    pub fn apply(&self, image: &Image) -> Image {
+        let mut mapped = Image::new(image.width(), image.height(), Color::default());
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                mapped.set_color(x, y, self.map_color(&image.get_color(x, y)));
+            }
+        }
+        mapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grey(mapper: &ToneMapper, value: f64) -> f64 {
+        mapper.map_color(&Color::new(value, value, value)).r
+    }
+
+    #[test]
+    fn test_operators() {
+        let clamp = ToneMapper::default();
+        assert_eq!(grey(&clamp, 0.25), 0.25);
+        assert_eq!(grey(&clamp, 3.0), 1.0);
+        assert_eq!(grey(&clamp, -1.0), 0.0);
+
+        assert_eq!(grey(&ToneMapper::new(ToneMapping::Reinhard), 1.0), 0.5);
+        let extended = ToneMapper::new(ToneMapping::ExtendedReinhard).with_white(2.0);
+        assert!((grey(&extended, 2.0) - 1.0).abs() < 1e-12);
+        assert!(grey(&extended, 1.0) > 0.5);
+
+        // The filmic curves start from black and saturate to white, always increasing
+        for operator in &[ToneMapping::Aces, ToneMapping::Uncharted2] {
+            let mapper = ToneMapper::new(*operator);
+            assert!(grey(&mapper, 0.0).abs() < 1e-12);
+            assert_eq!(grey(&mapper, 100.0), 1.0);
+            let values: Vec<f64> = (0..50).map(|i| grey(&mapper, i as f64 * 0.1)).collect();
+            assert!(values.windows(2).all(|pair| pair[0] < pair[1] || pair[1] == 1.0));
+        }
+        assert!((grey(&ToneMapper::new(ToneMapping::Uncharted2), 5.6) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_exposure_and_srgb() {
+        let brighter = ToneMapper::default().with_exposure(1.0);
+        assert_eq!(grey(&brighter, 0.25), 0.5);
+        let srgb = ToneMapper::default().with_srgb(true);
+        assert!((grey(&srgb, 0.5) - 0.7354).abs() < 1e-4);
+        assert!((grey(&srgb, 0.001) - 0.01292).abs() < 1e-12);
+        assert!((grey(&srgb, 1.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_names() {
+        let operators: Vec<ToneMapping> = ToneMapping::NAMES.iter().map(|name| name.parse().unwrap()).collect();
+        for (i, operator) in operators.iter().enumerate() {
+            assert!(!operators[i + 1..].contains(operator));
+        }
+        assert!("hejl".parse::<ToneMapping>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut image = Image::new(2, 1, Color::new(2.0, 0.5, 0.0));
+        image.set_color(1, 0, Color::new(1.0, 1.0, 1.0));
+        let mapped = ToneMapper::new(ToneMapping::Reinhard).apply(&image);
+        assert_eq!(mapped.get_color(0, 0), Color::new(2.0 / 3.0, 0.5 / 1.5, 0.0));
+        assert_eq!(mapped.get_color(1, 0), Color::new(0.5, 0.5, 0.5));
+    }
+}
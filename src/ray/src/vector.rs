@@ -1,5 +1,7 @@
-use math::{vector2d::Vec2d, vector3d::Vec3d};
+use math::{aabb::Aabb, transform::Transform as Affine, vector2d::Vec2d, vector3d::Vec3d};
 
 
 pub type Vector3d = Vec3d<f64>;
-pub type Vector2d = Vec2d<f64>;
\ No newline at end of file
+pub type Vector2d = Vec2d<f64>;
+pub type BoundingBox = Aabb<f64>;
+pub type Transform = Affine<f64>;
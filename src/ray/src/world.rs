@@ -1,37 +1,84 @@
-use crate::{camera::Camera, color::Color, light::Light, things::Thing};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
-use serde::{Deserialize, Serialize};
+use crate::{cameras::Camera, color::Color, error::ResourceError, integrators::IntegratorKind, lights::{AreaLight, LightSource}, sampling::Sampler, things::Thing};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Named geometries are deserialized as boxed things then shared by their instances.
+mod shared_things {
+    use super::*;
+
+    pub fn serialize<S>(things: &HashMap<String, Arc<dyn Thing>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(things.iter().map(|(name, thing)| (name, thing.as_ref())))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<String, Arc<dyn Thing>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let things: HashMap<String, Box<dyn Thing>> = HashMap::deserialize(deserializer)?;
+        Ok(things.into_iter().map(|(name, thing)| (name, Arc::from(thing))).collect())
+    }
+}
 
 #[derive(Deserialize,Serialize)]  
 pub struct World {
-    camera: Camera,
+    camera: Box<dyn Camera>,
     things: Vec<Box<dyn Thing>>,
-    lights: Vec<Light>,
+    // Geometries referenced by `instance` things, not rendered by themselves
+    #[serde(default, skip_serializing_if = "HashMap::is_empty", with = "shared_things")]
+    geometries: HashMap<String, Arc<dyn Thing>>,
+    lights: Vec<Box<dyn LightSource>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    area_lights: Vec<AreaLight>,
     ambiant_light: Color,
     max_recurions: u16,
+    #[serde(default)]
+    sampling: Sampler,
+    #[serde(default)]
+    integrator: IntegratorKind,
 }
 
 impl World {
-    pub fn new(camera: Camera, things: Vec<Box<dyn Thing>>, lights: Vec<Light>,ambiant_light: Color,max_recurions: u16) -> Self {
+    pub fn new(camera: Box<dyn Camera>, things: Vec<Box<dyn Thing>>, lights: Vec<Box<dyn LightSource>>,ambiant_light: Color,max_recurions: u16) -> Self {
         Self {
             camera,
             things,
+            geometries: HashMap::new(),
             lights,
+            area_lights: Vec::new(),
             ambiant_light,
             max_recurions,
+            sampling: Sampler::default(),
+            integrator: IntegratorKind::default(),
         }
     }
 
-    pub fn camera(&self) -> &Camera {
-        &self.camera
+    pub fn camera(&self) -> &dyn Camera {
+        self.camera.as_ref()
     }
 
     pub fn things(&self) -> &Vec<Box<dyn Thing>> {
         &self.things
     }
 
-    pub fn thing(&self,index: usize) -> &Box<dyn Thing> {
-        &self.things[index]
+    pub fn geometries(&self) -> &HashMap<String, Arc<dyn Thing>> {
+        &self.geometries
+    }
+
+    pub fn add_geometry(&mut self, name: String, geometry: Box<dyn Thing>) {
+        self.geometries.insert(name, Arc::from(geometry));
+    }
+
+    pub fn add_thing(&mut self, thing: Box<dyn Thing>) {
+        self.things.push(thing);
+    }
+
+    pub fn thing(&self,index: usize) -> &dyn Thing {
+        self.things[index].as_ref()
     }
 
 
@@ -39,11 +86,51 @@ impl World {
         &self.ambiant_light
     }
 
-    pub fn lights(&self) -> &Vec<Light> {
+    pub fn lights(&self) -> &Vec<Box<dyn LightSource>> {
         &self.lights
     }
 
+    pub fn area_lights(&self) -> &Vec<AreaLight> {
+        &self.area_lights
+    }
+
+    pub fn add_area_light(&mut self, light: AreaLight) {
+        self.area_lights.push(light);
+    }
+
     pub fn max_recurions(&self) -> u16 {
         self.max_recurions
     }
+
+    pub fn sampling(&self) -> &Sampler {
+        &self.sampling
+    }
+
+    pub fn set_sampling(&mut self, sampling: Sampler) {
+        self.sampling = sampling;
+    }
+
+    pub fn integrator(&self) -> IntegratorKind {
+        self.integrator
+    }
+
+    pub fn set_integrator(&mut self, integrator: IntegratorKind) {
+        self.integrator = integrator;
+    }
+
+    // Must be called once after deserialization so that things can load their external files.
+    pub fn load_resources(&mut self, scene_dir: &Path) -> Result<(), ResourceError> {
+        self.geometries.iter_mut().try_for_each(|(name, geometry)| {
+            Arc::get_mut(geometry)
+                .ok_or_else(|| ResourceError::GeometryShared(name.clone()))?
+                .load_resources(scene_dir)
+        })?;
+        self.things
+            .iter_mut()
+            .try_for_each(|thing| thing.load_resources(scene_dir))?;
+        let geometries = &self.geometries;
+        self.things
+            .iter_mut()
+            .try_for_each(|thing| thing.link_geometries(geometries))
+    }
 }
\ No newline at end of file
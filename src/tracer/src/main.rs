@@ -1,9 +1,21 @@
 
-use std::{fs::File, io::BufReader, time::Instant};
+use std::{fs::File, io::BufReader, path::Path, process, thread, time::Instant};
 
 use clap::{App, Arg};
 use pixel_canvas::Canvas;
-use ray::{engine::Engine, image::Image, world::World};
+use ray::{
+    encoders::{encoder_for_path, save_image, EncoderOptions, ExrCompression, ExrPrecision},
+    engine::Engine,
+    image::Image,
+    integrators::IntegratorKind,
+    sampling::{Filter, SamplePattern},
+    tone_mapping::{ToneMapper, ToneMapping},
+    world::World,
+};
+
+const EXIT_INVALID_ARGS: i32 = 1;
+const EXIT_INVALID_SCENE: i32 = 2;
+const EXIT_OUTPUT_FAILED: i32 = 3;
 
 fn main() {
     println!("Running raytracer...");
@@ -15,21 +27,189 @@ fn main() {
                  .short("f")
                  .long("file")
                  .takes_value(true)
+                 .required(true)
                  .help("world to render (yaml format)"))
+        .arg(Arg::with_name("output")
+                 .short("o")
+                 .long("output")
+                 .takes_value(true)
+                 .help("write the rendered image to this file (.png, .ppm, or .hdr and .exr for the linear radiance)"))
+        .arg(Arg::with_name("ascii")
+                 .long("ascii")
+                 .help("write ppm files in ascii (P3) instead of binary (P6)"))
+        .arg(Arg::with_name("exr-precision")
+                 .long("exr-precision")
+                 .takes_value(true)
+                 .possible_values(&ExrPrecision::NAMES)
+                 .help("size of the exr samples (defaults to half)"))
+        .arg(Arg::with_name("exr-compression")
+                 .long("exr-compression")
+                 .takes_value(true)
+                 .possible_values(&ExrCompression::NAMES)
+                 .help("compression of the exr scanlines (defaults to zip)"))
+        .arg(Arg::with_name("threads")
+                 .short("t")
+                 .long("threads")
+                 .takes_value(true)
+                 .help("number of render threads (defaults to the number of cores)"))
+        .arg(Arg::with_name("samples")
+                 .short("s")
+                 .long("samples")
+                 .takes_value(true)
+                 .help("number of samples per pixel (overrides the world sampling)"))
+        .arg(Arg::with_name("pattern")
+                 .long("pattern")
+                 .takes_value(true)
+                 .possible_values(&["regular", "jittered", "halton", "sobol"])
+                 .help("sub-pixel sample pattern (overrides the world sampling)"))
+        .arg(Arg::with_name("filter")
+                 .long("filter")
+                 .takes_value(true)
+                 .possible_values(&["box", "tent", "gaussian", "mitchell"])
+                 .help("reconstruction filter (overrides the world sampling)"))
+        .arg(Arg::with_name("integrator")
+                 .long("integrator")
+                 .takes_value(true)
+                 .possible_values(&IntegratorKind::NAMES)
+                 .help("rendering algorithm (overrides the world integrator)"))
+        .arg(Arg::with_name("tone-map")
+                 .long("tone-map")
+                 .takes_value(true)
+                 .possible_values(&ToneMapping::NAMES)
+                 .help("curve bringing the rendered radiance to displayable values (defaults to clamp)"))
+        .arg(Arg::with_name("exposure")
+                 .long("exposure")
+                 .takes_value(true)
+                 .allow_hyphen_values(true)
+                 .help("exposure in stops applied before the tone mapping (defaults to 0)"))
+        .arg(Arg::with_name("white")
+                 .long("white")
+                 .takes_value(true)
+                 .help("radiance mapped to white by the extended_reinhard tone mapping (defaults to 4)"))
+        .arg(Arg::with_name("srgb")
+                 .long("srgb")
+                 .help("encode the displayed image and the png or ppm output with the sRGB gamma curve \
+                        (off by default, the tone mapped values are written linearly)"))
+        .arg(Arg::with_name("no-display")
+                 .long("no-display")
+                 .help("do not open a window to display the image"))
         .get_matches();
-    
+
+    // Required argument, checked by clap
     let yaml_file = matches.value_of("file").unwrap();
-    let file = File::open(yaml_file).unwrap();
-    let reader = BufReader::new(file);
+    let mut world = load_world(yaml_file).unwrap_or_else(|message| {
+        eprintln!("Unable to load world {}: {}", yaml_file, message);
+        process::exit(EXIT_INVALID_SCENE)
+    });
+
+    let mut encoder_options = EncoderOptions {
+        ascii: matches.is_present("ascii"),
+        ..EncoderOptions::default()
+    };
+    if let Some(precision) = matches.value_of("exr-precision") {
+        encoder_options.exr_precision = precision.parse::<ExrPrecision>().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+    if let Some(compression) = matches.value_of("exr-compression") {
+        encoder_options.exr_compression = compression.parse::<ExrCompression>().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+    let output = matches.value_of("output").map(|output| {
+        let encoder = encoder_for_path(Path::new(output), &encoder_options).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+        (output, encoder)
+    });
+
+    let threads = match matches.value_of("threads") {
+        Some(threads) => threads.parse::<usize>().ok().filter(|t| *t > 0).unwrap_or_else(|| {
+            eprintln!("Invalid thread count: {}", threads);
+            process::exit(EXIT_INVALID_ARGS)
+        }),
+        None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    };
+
+    let mut sampling = world.sampling().clone();
+    if let Some(samples) = matches.value_of("samples") {
+        sampling.samples_per_pixel = samples.parse::<usize>().ok().filter(|s| *s > 0).unwrap_or_else(|| {
+            eprintln!("Invalid sample count: {}", samples);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+    if let Some(pattern) = matches.value_of("pattern") {
+        sampling.pattern = pattern.parse::<SamplePattern>().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+    if let Some(filter) = matches.value_of("filter") {
+        sampling.filter = filter.parse::<Filter>().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+    world.set_sampling(sampling);
+    if let Some(integrator) = matches.value_of("integrator") {
+        world.set_integrator(integrator.parse::<IntegratorKind>().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(EXIT_INVALID_ARGS)
+        }));
+    }
 
-    let world: World = serde_yaml::from_reader(reader).unwrap();
-    let engine = Engine::new(world);
+    let mut tone_mapper = ToneMapper::default().with_srgb(matches.is_present("srgb"));
+    if let Some(operator) = matches.value_of("tone-map") {
+        tone_mapper.operator = operator.parse::<ToneMapping>().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+    if let Some(exposure) = matches.value_of("exposure") {
+        tone_mapper.exposure = exposure.parse::<f64>().ok().filter(|e| e.is_finite()).unwrap_or_else(|| {
+            eprintln!("Invalid exposure: {}", exposure);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+    if let Some(white) = matches.value_of("white") {
+        tone_mapper.white = white.parse::<f64>().ok().filter(|w| *w > 0.0).unwrap_or_else(|| {
+            eprintln!("Invalid white point: {}", white);
+            process::exit(EXIT_INVALID_ARGS)
+        });
+    }
+
+    let engine = Engine::with_threads(world, threads);
     let start = Instant::now();
-    let image_to_display = engine.generate();
+    // The engine renders the linear radiance, tone mapped for the output
+    let rendered = engine.generate();
     let duration = start.elapsed();
     println!("Time elapsed: {:?}", duration);
+    let image_to_display = tone_mapper.apply(&rendered);
+
+    if let Some((output, encoder)) = output {
+        let image = if encoder.is_linear() { &rendered } else { &image_to_display };
+        if let Err(error) = save_image(image, Path::new(output), encoder.as_ref()) {
+            eprintln!("Unable to write {}: {}", output, error);
+            process::exit(EXIT_OUTPUT_FAILED);
+        }
+        println!("Image written to {}", output);
+    }
 
-    display_image(image_to_display);
+    if !matches.is_present("no-display") {
+        display_image(image_to_display);
+    }
+}
+
+fn load_world(yaml_file: &str) -> Result<World, String> {
+    let file = File::open(yaml_file).map_err(|e| e.to_string())?;
+    let reader = BufReader::new(file);
+    let mut world: World = serde_yaml::from_reader(reader).map_err(|e| e.to_string())?;
+    let scene_dir = Path::new(yaml_file).parent().unwrap_or_else(|| Path::new(""));
+    world.load_resources(scene_dir).map_err(|e| e.to_string())?;
+    Ok(world)
 }
 
 pub fn display_image(image_to_display: Image) {
@@ -39,8 +219,8 @@ pub fn display_image(image_to_display: Image) {
     )
     .title("Ray");
     canvas.render(move |_mouse, image| {
-        let width = image.width() as usize;
-        let height = image.height() as usize;
+        let width = image.width();
+        let height = image.height();
 
         for (y, row) in image.chunks_mut(width).enumerate() {
             for (x, pixel) in row.iter_mut().enumerate() {